@@ -0,0 +1,20 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+
+extern crate bytes;
+extern crate prost;
+#[macro_use] extern crate prost_derive;
+
+// The decoder only depends on the generated protobuf code,
+// so it is compiled straight from the client sources.
+#[path = "../../src/proto.rs"]
+#[allow(dead_code)]
+mod proto;
+#[path = "../../src/msg.rs"]
+#[allow(dead_code)]
+mod msg;
+
+fuzz_target!(|data: &[u8]| {
+    // must never panic, whatever comes in
+    let _ = msg::Msg::from_bytes(data);
+});
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use ggez::graphics::Point2;
+use specs::Entity;
+
+use components::Body;
+use constant;
+use resources::Arena;
+
+
+/// Broad phase: bodies are bucketed into square cells,
+/// so a query only looks at the bodies sharing a cell with it.
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<Entity>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, pos: Point2, radius: f32) {
+        for cell in self.cells_around(pos, radius) {
+            self.cells.entry(cell).or_insert_with(Vec::new).push(entity);
+        }
+    }
+
+    /// Entities in the cells touched by the circle, each once.
+    /// These are only candidates, `overlap` tells the actual contacts.
+    pub fn query(&self, pos: Point2, radius: f32) -> Vec<Entity> {
+        let mut found = Vec::new();
+
+        for cell in self.cells_around(pos, radius) {
+            if let Some(entities) = self.cells.get(&cell) {
+                found.extend(entities.iter().cloned());
+            }
+        }
+
+        // big bodies sit in several cells
+        found.sort();
+        found.dedup();
+
+        found
+    }
+
+    fn cells_around(&self, pos: Point2, radius: f32) -> Vec<(i32, i32)> {
+        // a bogus size from the server mustn't blow up the number of cells
+        let radius = radius.min(self.cell_size * constant::collision::MAX_CELLS_ACROSS / 2.0);
+
+        let min_x = ((pos.x - radius) / self.cell_size).floor() as i32;
+        let max_x = ((pos.x + radius) / self.cell_size).floor() as i32;
+        let min_y = ((pos.y - radius) / self.cell_size).floor() as i32;
+        let max_y = ((pos.y + radius) / self.cell_size).floor() as i32;
+
+        let columns = max_x - min_x + 1;
+        let rows = max_y - min_y + 1;
+
+        let mut cells = Vec::new();
+        for x in min_x..min_x + columns {
+            for y in min_y..min_y + rows {
+                cells.push((x, y));
+            }
+        }
+
+        cells
+    }
+}
+
+
+/// Narrow phase: bodies are circles `size` across.
+/// In a wrapping arena they also touch across the edge.
+pub fn overlap(a: &Body, b: &Body, arena: &Arena) -> bool {
+    let distance = (arena.unwrap(a.pos, b.pos) - a.pos).norm();
+
+    distance < (a.size / 2.0 + b.size / 2.0)
+}
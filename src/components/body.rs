@@ -7,7 +7,10 @@ use ggez::graphics::{
 
 use constant::physics;
 use proto::astero;
+use resources::Arena;
 use util::{
+    lerp_angle,
+    normalize_angle,
     reflect_vector,
     vec_from_angle,
 };
@@ -51,7 +54,7 @@ impl Body {
         Self {
             pos: body.pos.into(),
             vel: body.vel.into(),
-            rot: body.rot.unwrap_or(0.0),
+            rot: normalize_angle(body.rot.unwrap_or(0.0)),
             rvel: body.rvel.unwrap_or(0.0),
             size: body.size.unwrap_or(0.0),
         }
@@ -68,10 +71,10 @@ impl Body {
     }
 
     pub fn rotate(&mut self, dt: f32, direction: i32) {
-        self.rot += self.rvel * dt * direction as f32;
+        self.rot = normalize_angle(self.rot + self.rvel * dt * direction as f32);
     }
 
-    pub fn wrap_position(&mut self, xb: f32, yb: f32) {
+    pub fn bounce_off_edges(&mut self, xb: f32, yb: f32) {
         let center = self.pos + Vector2::new(self.pos.x.signum() * self.size / 2.0, self.pos.y.signum() * self.size / 2.0);
 
         let (nx, ny) = match center {
@@ -106,7 +109,8 @@ impl Body {
     pub fn interpolate_to(&mut self, other: &Self, by_dt: f32) {
         self.pos.x += (other.pos.x - self.pos.x) * by_dt;
         self.pos.y += (other.pos.y - self.pos.y) * by_dt;
-        self.rot += (other.rot - self.rot) * by_dt;
+        self.rot = lerp_angle(self.rot, other.rot, by_dt);
+        self.rvel += (other.rvel - self.rvel) * by_dt;
     }
 }
 
@@ -129,13 +133,16 @@ impl BlenderBody {
         self.prev = Some(body);
     }
 
-    pub fn blend(&mut self, cur: &Body, blending_factor: f32) {
+    pub fn blend(&mut self, cur: &Body, blending_factor: f32, arena: &Arena) {
         if let Some(ref prev_body) = self.prev {
             let mut blended = cur.clone();
+            let cur_pos = arena.unwrap(prev_body.pos, cur.pos);
 
-            blended.pos.x = cur.pos.x * blending_factor + prev_body.pos.x * (1.0 - blending_factor);
-            blended.pos.y = cur.pos.y * blending_factor + prev_body.pos.y * (1.0 - blending_factor);
-            blended.rot = cur.rot * blending_factor + prev_body.rot * (1.0 - blending_factor);
+            blended.pos.x = cur_pos.x * blending_factor + prev_body.pos.x * (1.0 - blending_factor);
+            blended.pos.y = cur_pos.y * blending_factor + prev_body.pos.y * (1.0 - blending_factor);
+            blended.pos = arena.wrap(blended.pos);
+            blended.rot = lerp_angle(prev_body.rot, cur.rot, blending_factor);
+            blended.rvel = cur.rvel * blending_factor + prev_body.rvel * (1.0 - blending_factor);
 
             self.blended = Some(blended);
         } else {
@@ -0,0 +1,47 @@
+use ggez::{
+    graphics,
+    Context,
+    GameResult,
+};
+
+use resources::Assets;
+
+
+/// Amount of damage floating up from where it was taken.
+#[derive(Component, Debug)]
+pub struct DamageNumber {
+    text: String,
+}
+
+impl DamageNumber {
+    pub fn new(amount: f32) -> Self {
+        Self {
+            text: format!("-{}", amount),
+        }
+    }
+
+    pub fn draw(
+        &self,
+        ctx: &mut Context, assets: &mut Assets,
+        pos: graphics::Point2, color: graphics::Color
+    ) -> GameResult<()> {
+        let display = assets.get_text(ctx, &self.text)?;
+
+        let dest = graphics::Point2::new(
+            pos.x - (display.width() / 2) as f32,
+            pos.y - (display.height() / 2) as f32,
+        );
+
+        graphics::draw_ex(
+            ctx,
+            display,
+            graphics::DrawParam {
+                dest,
+                color: Some(color),
+                .. Default::default()
+            }
+        )?;
+
+        Ok(())
+    }
+}
@@ -18,9 +18,25 @@ impl Sticky {
     pub fn draw(
         &self,
         ctx: &mut Context,
-        pos: graphics::Point2, size: f32, fraction: f32, color: graphics::Color
+        pos: graphics::Point2, size: f32, fraction: f32, trail_fraction: f32, color: graphics::Color
     ) -> GameResult<()> {
         let old_color = graphics::get_color(ctx);
+
+        // recent damage, under the current life
+        if trail_fraction > fraction {
+            let trail_width = size * trail_fraction;
+
+            graphics::set_color(ctx, colors::WHITE)?;
+            graphics::rectangle(
+                ctx, graphics::DrawMode::Fill,
+                graphics::Rect::new(
+                    pos.x - trail_width / 2.0,
+                    pos.y + size / 2.0 + STICKY_HEALTH_BAR_HEIGHT,
+                    trail_width, STICKY_HEALTH_BAR_HEIGHT
+                )
+            )?;
+        }
+
         graphics::set_color(ctx, color)?;
 
         let width = size * fraction;
@@ -54,7 +70,7 @@ impl Static {
         Self {x, y, width, height}
     }
 
-    pub fn draw(&self, ctx: &mut Context, fraction: f32) -> GameResult<()> {
+    pub fn draw(&self, ctx: &mut Context, fraction: f32, trail_fraction: f32) -> GameResult<()> {
         let old_color = graphics::get_color(ctx);
 
         graphics::set_color(ctx, colors::LIGHT_BLUE)?;
@@ -64,13 +80,30 @@ impl Static {
             graphics::Rect::new(self.x, self.y, self.width, self.height)
         )?;
 
-        graphics::set_color(ctx, colors::RED)?;
-
-        let width = (self.width - STATIC_HEALTH_BAR_LINE_WIDTH) * fraction;
+        let inner_width = self.width - STATIC_HEALTH_BAR_LINE_WIDTH;
+        let width = inner_width * fraction;
         let height = self.height - STATIC_HEALTH_BAR_LINE_WIDTH;
 
         let half_line_width = STATIC_HEALTH_BAR_LINE_WIDTH / 2.0;
 
+        // recent damage, under the current life
+        if trail_fraction > fraction {
+            let trail_width = inner_width * trail_fraction;
+
+            graphics::set_color(ctx, colors::WHITE)?;
+            graphics::rectangle(
+                ctx, graphics::DrawMode::Fill,
+                graphics::Rect::new(
+                    self.x + self.width / 2.0 - trail_width / 2.0,
+                    self.y + half_line_width,
+                    trail_width,
+                    height
+                )
+            )?;
+        }
+
+        graphics::set_color(ctx, colors::RED)?;
+
         graphics::rectangle(
             ctx, graphics::DrawMode::Fill,
             graphics::Rect::new(
@@ -0,0 +1,29 @@
+use constant::hud::HIT_FLASH_DURATION;
+
+
+/// Entity is drawn tinted for a moment after taking damage.
+#[derive(Component, Debug)]
+pub struct HitFlash {
+    remaining: f32,
+}
+
+impl HitFlash {
+    pub fn new() -> Self {
+        Self {
+            remaining: HIT_FLASH_DURATION,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.remaining -= dt;
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.remaining <= 0.0
+    }
+
+    /// 1.0 right after the hit, fading to 0.0.
+    pub fn intensity(&self) -> f32 {
+        (self.remaining / HIT_FLASH_DURATION).max(0.0).min(1.0)
+    }
+}
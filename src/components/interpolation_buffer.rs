@@ -1,8 +1,8 @@
 use std::collections::VecDeque;
 
 use components::Body;
-use proto::astero;
-use util::cur_time_in_millis;
+use constant::interpolation;
+use resources::Arena;
 
 
 #[derive(Debug)]
@@ -12,6 +12,7 @@ pub struct InterpolationPosition {
 }
 
 
+/// Samples of a remote entity ordered by timestamp (server time converted to the local clock).
 #[derive(Component, Debug)]
 pub struct InterpolationBuffer {
     buf: VecDeque<InterpolationPosition>,
@@ -24,23 +25,172 @@ impl InterpolationBuffer {
         }
     }
 
-    pub fn add(&mut self, body: &astero::Body) {
-        self.buf.push_back(InterpolationPosition {
-            timestamp: cur_time_in_millis(),
-            body: Body::new(body),
-        })
+    pub fn add(&mut self, timestamp: u64, body: Body) {
+        // late samples are put in place, a sample with a known timestamp replaces the old one
+        let idx = self.buf.iter()
+            .rposition(|position| position.timestamp <= timestamp);
+
+        match idx {
+            Some(idx) if self.buf[idx].timestamp == timestamp => self.buf[idx].body = body,
+            Some(idx) => self.buf.insert(idx + 1, InterpolationPosition { timestamp, body }),
+            None => self.buf.push_front(InterpolationPosition { timestamp, body }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.buf.clear();
     }
 
-    pub fn interpolate(&mut self, timestamp: u64) -> Body {
+    /// Body at `timestamp`, `None` until the first sample arrives.
+    /// Before the first sample the first one is used, after the last one
+    /// the body is moved along its velocity for at most `MAX_EXTRAPOLATION_MS`.
+    pub fn interpolate(&mut self, timestamp: u64, arena: &Arena) -> Option<Body> {
+        // keep one sample at or before `timestamp` to interpolate from
         while self.buf.len() >= 2 && self.buf[1].timestamp <= timestamp {
             self.buf.pop_front();
         }
 
-        let dt = (timestamp - self.buf[0].timestamp) as f32 / (self.buf[1].timestamp - self.buf[0].timestamp) as f32;
+        let from = self.buf.front()?;
+
+        if timestamp <= from.timestamp {
+            return Some(from.body.clone());
+        }
+
+        let mut body = from.body.clone();
+
+        match self.buf.get(1) {
+            Some(to) => {
+                // `from` is before `timestamp` and `to` is after it, timestamps are distinct
+                let dt = (timestamp - from.timestamp) as f32 / (to.timestamp - from.timestamp) as f32;
+
+                let mut to_body = to.body.clone();
+                to_body.pos = arena.unwrap(from.body.pos, to_body.pos);
+                body.interpolate_to(&to_body, dt);
+            }
+            None => {
+                let ahead = (timestamp - from.timestamp).min(interpolation::MAX_EXTRAPOLATION_MS);
+                body.pos += body.vel * (ahead as f32 / 1000.0);
+            }
+        }
+
+        body.pos = arena.wrap(body.pos);
+
+        Some(body)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ggez::graphics::{
+        Point2,
+        Vector2,
+    };
+
+    use components::Body;
+    use constant::interpolation::MAX_EXTRAPOLATION_MS;
+    use resources::{
+        Arena,
+        Topology,
+    };
+
+    use super::InterpolationBuffer;
+
+
+    fn arena() -> Arena {
+        Arena::new(800.0, 600.0, Topology::Bounce)
+    }
+
+    fn body_at(x: f32, vx: f32) -> Body {
+        Body {
+            pos: Point2::new(x, 0.0),
+            vel: Vector2::new(vx, 0.0),
+            ..Default::default()
+        }
+    }
+
+    fn assert_x(buf: &mut InterpolationBuffer, timestamp: u64, x: f32) {
+        let body = buf.interpolate(timestamp, &arena()).expect("Buffer has samples");
+        assert!((body.pos.x - x).abs() < 1e-3, "{} at {}, expected {}", body.pos.x, timestamp, x);
+    }
+
+    #[test]
+    fn empty_buffer_has_no_body() {
+        let mut buf = InterpolationBuffer::new();
+
+        assert!(buf.interpolate(0, &arena()).is_none());
+        assert!(buf.interpolate(1000, &arena()).is_none());
+    }
+
+    #[test]
+    fn single_sample_is_used_and_extrapolated() {
+        let mut buf = InterpolationBuffer::new();
+        buf.add(1000, body_at(10.0, 100.0));
+
+        assert_x(&mut buf, 1000, 10.0);
+        assert_x(&mut buf, 1100, 20.0);
+        assert_eq!(buf.len(), 1);
+    }
+
+    #[test]
+    fn before_first_sample_the_first_one_is_used() {
+        let mut buf = InterpolationBuffer::new();
+        buf.add(1000, body_at(10.0, 100.0));
+        buf.add(1100, body_at(20.0, 100.0));
+
+        // no underflow for render times older than every sample
+        assert_x(&mut buf, 0, 10.0);
+        assert_x(&mut buf, 999, 10.0);
+        assert_eq!(buf.len(), 2);
+    }
+
+    #[test]
+    fn between_samples_is_interpolated() {
+        let mut buf = InterpolationBuffer::new();
+        buf.add(1000, body_at(10.0, 0.0));
+        buf.add(1100, body_at(20.0, 0.0));
+
+        assert_x(&mut buf, 1050, 15.0);
+    }
+
+    #[test]
+    fn after_last_sample_extrapolation_is_bounded() {
+        let mut buf = InterpolationBuffer::new();
+        buf.add(1000, body_at(0.0, 0.0));
+        buf.add(1100, body_at(10.0, 100.0));
+
+        // 100 ms past the last sample at 100 units per second
+        assert_x(&mut buf, 1200, 20.0);
+        assert_eq!(buf.len(), 1);
+
+        let max_ahead = 10.0 + 100.0 * MAX_EXTRAPOLATION_MS as f32 / 1000.0;
+        assert_x(&mut buf, 1100 + 10 * MAX_EXTRAPOLATION_MS, max_ahead);
+    }
+
+    #[test]
+    fn duplicate_timestamp_replaces_the_sample() {
+        let mut buf = InterpolationBuffer::new();
+        buf.add(1000, body_at(10.0, 0.0));
+        buf.add(1100, body_at(20.0, 0.0));
+        buf.add(1100, body_at(30.0, 0.0));
+
+        assert_eq!(buf.len(), 2);
+        assert_x(&mut buf, 1050, 20.0);
+        assert_x(&mut buf, 1100, 30.0);
+    }
 
-        let mut body = self.buf[0].body.clone();
-        body.interpolate_to(&self.buf[1].body, dt);
+    #[test]
+    fn late_sample_is_put_in_place() {
+        let mut buf = InterpolationBuffer::new();
+        buf.add(1000, body_at(10.0, 0.0));
+        buf.add(1200, body_at(30.0, 0.0));
+        buf.add(1100, body_at(20.0, 0.0));
 
-        body
+        assert_eq!(buf.len(), 3);
+        assert_x(&mut buf, 1150, 25.0);
     }
 }
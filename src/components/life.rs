@@ -1,7 +1,12 @@
+use constant::hud::DAMAGE_TRAIL_SPEED;
+
+
 #[derive(Component, Debug)]
 pub struct Life {
     cur: f32,
     max: f32,
+    // life before recent damage, catches up with `cur` over time
+    trail: f32,
 }
 
 impl Life {
@@ -9,24 +14,68 @@ impl Life {
         Self {
             cur: max,
             max,
+            trail: max,
         }
     }
 
+    /// Sets life as told by the server, returns the damage taken (negative when healed).
+    pub fn set(&mut self, cur: f32) -> f32 {
+        let damage = self.cur - cur;
+
+        self.cur = cur;
+        self.max = self.max.max(cur);
+        self.trail = self.trail.max(cur);
+
+        damage
+    }
+
+    pub fn update_trail(&mut self, dt: f32) {
+        self.trail = (self.trail - self.max * DAMAGE_TRAIL_SPEED * dt).max(self.cur);
+    }
+
     pub fn fraction(&self) -> f32 {
         self.cur / self.max
     }
+
+    pub fn trail_fraction(&self) -> f32 {
+        self.trail / self.max
+    }
 }
 
 
 #[derive(Component, Debug)]
 pub struct TimeToLive {
-    ttl: f32
+    ttl: f32,
+    max: f32,
 }
 
 impl TimeToLive {
     pub fn new(ttl: f32) -> Self {
+        Self::with_remaining(ttl, ttl)
+    }
+
+    /// Part of `max` is already gone.
+    pub fn with_remaining(ttl: f32, max: f32) -> Self {
         Self {
-            ttl
+            ttl,
+            max,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.ttl -= dt;
+    }
+
+    pub fn expired(&self) -> bool {
+        self.ttl <= 0.0
+    }
+
+    /// Remaining part of the lifetime, 1.0 when fresh, 0.0 when expired.
+    pub fn fraction(&self) -> f32 {
+        if self.max > 0.0 {
+            (self.ttl / self.max).max(0.0).min(1.0)
+        } else {
+            0.0
         }
     }
 }
@@ -0,0 +1,93 @@
+use ggez::{
+    graphics,
+    Context,
+    GameResult,
+};
+
+use constant::{
+    colors,
+    hud::MINIMAP_ARROW_SIZE,
+    hud::MINIMAP_LINE_WIDTH,
+    hud::MINIMAP_MIN_DOT_RADIUS,
+};
+use resources::Arena;
+
+
+/// Whole arena scaled down into a corner of the window,
+/// plus arrows at the window edges pointing to players out of view.
+#[derive(Component, Debug)]
+pub struct Minimap {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl Minimap {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {x, y, width, height}
+    }
+
+    /// `dots` are world position, size and color of each entity.
+    pub fn draw<I>(&self, ctx: &mut Context, arena: &Arena, dots: I) -> GameResult<()>
+        where I: Iterator<Item=(graphics::Point2, f32, graphics::Color)>
+    {
+        let old_color = graphics::get_color(ctx);
+
+        // arena keeps its aspect ratio inside the minimap
+        let scale = (self.width / arena.width).min(self.height / arena.height);
+        let center = graphics::Point2::new(self.x + self.width / 2.0, self.y + self.height / 2.0);
+
+        graphics::set_color(ctx, colors::LIGHT_BLUE)?;
+        graphics::rectangle(
+            ctx, graphics::DrawMode::Line(MINIMAP_LINE_WIDTH),
+            graphics::Rect::new(
+                center.x - arena.width * scale / 2.0,
+                center.y - arena.height * scale / 2.0,
+                arena.width * scale,
+                arena.height * scale
+            )
+        )?;
+
+        for (pos, size, color) in dots {
+            let dot = graphics::Point2::new(center.x + pos.x * scale, center.y - pos.y * scale);
+            let radius = (size * scale / 2.0).max(MINIMAP_MIN_DOT_RADIUS);
+
+            graphics::set_color(ctx, color)?;
+            graphics::circle(ctx, graphics::DrawMode::Fill, dot, radius, 0.5)?;
+        }
+
+        graphics::set_color(ctx, old_color)?;
+
+        Ok(())
+    }
+
+    /// Arrow at the window edge in the direction of `target` (in screen coordinates, off screen).
+    pub fn draw_edge_arrow(&self, ctx: &mut Context, target: graphics::Point2, color: graphics::Color) -> GameResult<()> {
+        let half_width = ctx.conf.window_mode.width as f32 / 2.0;
+        let half_height = ctx.conf.window_mode.height as f32 / 2.0;
+        let center = graphics::Point2::new(half_width, half_height);
+
+        let to_target = target - center;
+        let distance = to_target.norm();
+        if distance <= 0.0 {
+            return Ok(());
+        }
+        let dir = to_target / distance;
+
+        // the tip touches the window edge, inset by the arrow size
+        let reach_x = if dir.x != 0.0 { (half_width - MINIMAP_ARROW_SIZE) / dir.x.abs() } else { distance };
+        let reach_y = if dir.y != 0.0 { (half_height - MINIMAP_ARROW_SIZE) / dir.y.abs() } else { distance };
+        let tip = center + dir * reach_x.min(reach_y).min(distance);
+
+        let back = tip - dir * MINIMAP_ARROW_SIZE;
+        let side = graphics::Vector2::new(-dir.y, dir.x) * (MINIMAP_ARROW_SIZE / 2.0);
+
+        let old_color = graphics::get_color(ctx);
+        graphics::set_color(ctx, color)?;
+        graphics::polygon(ctx, graphics::DrawMode::Fill, &[tip, back + side, back - side])?;
+        graphics::set_color(ctx, old_color)?;
+
+        Ok(())
+    }
+}
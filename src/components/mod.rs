@@ -3,12 +3,17 @@ mod body;
 mod cannon;
 mod color;
 mod controllable;
+mod damage_number;
 mod health_bar;
+mod hit_flash;
 mod interpolation_buffer;
 mod life;
+mod minimap;
 mod network_id;
 mod nickname;
+mod predicted_shot;
 mod sprite;
+mod visual_offset;
 
 pub use self::accelerator::Accelerator;
 pub use self::body::{
@@ -18,10 +23,12 @@ pub use self::body::{
 pub use self::cannon::Cannon;
 pub use self::color::Color;
 pub use self::controllable::Controllable;
+pub use self::damage_number::DamageNumber;
 pub use self::{
     health_bar::Sticky as StickyHealthBar,
     health_bar::Static as StaticHealthBar,
 };
+pub use self::hit_flash::HitFlash;
 pub use self::interpolation_buffer::{
     InterpolationBuffer,
     InterpolationPosition,
@@ -30,9 +37,12 @@ pub use self::life::{
     Life,
     TimeToLive,
 };
+pub use self::minimap::Minimap;
 pub use self::network_id::{
     NetworkId,
     ShotNetworkId,
 };
 pub use self::nickname::Nickname;
+pub use self::predicted_shot::PredictedShot;
 pub use self::sprite::Sprite;
+pub use self::visual_offset::VisualOffset;
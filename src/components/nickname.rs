@@ -4,30 +4,36 @@ use ggez::{
     GameResult,
 };
 
+use resources::Assets;
+
 
 #[derive(Component, Debug)]
 pub struct Nickname {
-    display: graphics::Text,
+    name: String,
 }
 
 impl Nickname {
-    pub fn new(ctx: &mut Context, nickname: &str, font: &graphics::Font) -> GameResult<Self> {
-        let new = Self {
-            display: graphics::Text::new(ctx, nickname, font)?
-        };
-
-        Ok(new)
+    pub fn new(nickname: &str) -> Self {
+        Self {
+            name: nickname.to_string(),
+        }
     }
 
-    pub fn draw(&self, ctx: &mut Context, pos: graphics::Point2, size: f32, color: graphics::Color) -> GameResult<()> {
+    pub fn draw(
+        &self,
+        ctx: &mut Context, assets: &mut Assets,
+        pos: graphics::Point2, size: f32, color: graphics::Color
+    ) -> GameResult<()> {
+        let display = assets.get_text(ctx, &self.name)?;
+
         let dest = graphics::Point2::new(
-            pos.x - (self.display.width() / 2) as f32,
-            pos.y - size / 2.0 - self.display.height() as f32,
+            pos.x - (display.width() / 2) as f32,
+            pos.y - size / 2.0 - display.height() as f32,
         );
 
         graphics::draw_ex(
             ctx,
-            &self.display,
+            display,
             graphics::DrawParam {
                 dest,
                 color: Some(color),
@@ -0,0 +1,22 @@
+use std::time::Instant;
+
+use components::Body;
+
+
+/// Shot fired by the local player that the server hasn't created yet.
+/// Server shots carry no id, so they are matched to these by where and when they were fired.
+#[derive(Component, Debug)]
+pub struct PredictedShot {
+    // body the shot was fired with
+    pub origin: Body,
+    pub fired_at: Instant,
+}
+
+impl PredictedShot {
+    pub fn new(origin: Body) -> Self {
+        Self {
+            origin,
+            fired_at: Instant::now(),
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use ggez::graphics::Vector2;
+
+use constant::smoothing;
+
+
+/// Difference between where an entity is drawn and where it is,
+/// left over from a correction and shrinking every step so the entity glides into place.
+#[derive(Component, Debug)]
+pub struct VisualOffset(pub Vector2);
+
+impl VisualOffset {
+    pub fn decay(&mut self, dt: f32) {
+        self.0 *= (-dt / smoothing::VISUAL_OFFSET_DECAY_TIME).exp();
+
+        if self.0.norm_squared() < smoothing::VISUAL_OFFSET_EPSILON.powi(2) {
+            self.0 = Vector2::new(0.0, 0.0);
+        }
+    }
+}
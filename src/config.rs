@@ -0,0 +1,482 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs::File;
+#[cfg(feature = "fake-server")]
+use std::io::Read;
+use std::io::{
+    self,
+    BufRead,
+    BufReader,
+};
+use std::net::{
+    SocketAddr,
+    ToSocketAddrs,
+};
+use std::path::Path;
+use std::str;
+use std::time::Duration;
+
+#[cfg(feature = "fake-server")]
+use fake_server::{
+    self,
+    FakeServerConfig,
+    LinkConditions,
+};
+use constant;
+use resources::{
+    Arena,
+    JitterDistribution,
+    NetworkConditions,
+    Topology,
+};
+
+
+const DEFAULT_CONFIG_FILE: &str = "astero.conf";
+const ENV_PREFIX: &str = "ASTERO_";
+
+const DEFAULT_SERVER_HOST: &str = "::1";
+const DEFAULT_SERVER_PORT: u16 = 11_111;
+
+// a one-way trip on a good connection on top of the minimum
+const DEFAULT_INTERPOLATION_DELAY_MS: u64 = 100;
+// two server updates at 30 Hz
+const DEFAULT_INTERPOLATION_DELAY_MIN_MS: u64 = 2 * 1000 / 30;
+const DEFAULT_INTERPOLATION_DELAY_MAX_MS: u64 = 250;
+
+// Every setting can be given as `--key value` on the command line,
+// as `ASTERO_KEY=value` in the environment or as `key = value` in the config file.
+// Command line beats environment, environment beats config file.
+const KEYS: &[&str] = &[
+    "server",
+    "headless",
+    "duration",
+    "fake-server",
+    "fake-loss",
+    "fake-reorder",
+    "fake-delay",
+    "fake-asteroids",
+    "fake-script",
+    "record",
+    "replay",
+    "net-conditioner",
+    "net-latency",
+    "net-jitter",
+    "net-jitter-distribution",
+    "net-loss",
+    "net-duplication",
+    "net-reordering",
+    "interp-delay",
+    "interp-delay-min",
+    "interp-delay-max",
+    "arena-topology",
+    "arena-width",
+    "arena-height",
+];
+
+
+#[derive(Debug, Clone)]
+pub struct ServerAddress {
+    pub host: String,
+    pub port: u16,
+}
+
+impl ServerAddress {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+
+        let (host, port) = if s.starts_with('[') {
+            // [v6 address]:port or [v6 address]
+            let end = s.find(']')
+                .ok_or_else(|| format!("Unclosed '[' in server address '{}'", s))?;
+
+            let host = &s[1..end];
+            let rest = &s[end + 1..];
+
+            let port = if rest.is_empty() {
+                None
+            } else if rest.starts_with(':') {
+                Some(&rest[1..])
+            } else {
+                return Err(format!("Unexpected '{}' after ']' in server address '{}'", rest, s));
+            };
+
+            (host, port)
+        } else if s.matches(':').count() == 1 {
+            // host:port or v4:port
+            let idx = s.find(':').expect("Checked above");
+            (&s[..idx], Some(&s[idx + 1..]))
+        } else {
+            // bare host, v4 or v6 address
+            (s, None)
+        };
+
+        if host.is_empty() {
+            return Err(format!("Empty host in server address '{}'", s));
+        }
+
+        let port = match port {
+            Some(port) => port.parse::<u16>()
+                .map_err(|err| format!("Invalid port '{}' in server address '{}': {}", port, s, err))?,
+            None => DEFAULT_SERVER_PORT,
+        };
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+        })
+    }
+
+    pub fn resolve(&self) -> io::Result<SocketAddr> {
+        let mut addrs = (self.host.as_str(), self.port).to_socket_addrs()
+            .map_err(|err| io::Error::new(
+                err.kind(),
+                format!("Could not resolve server address {}: {}", self, err)
+            ))?;
+
+        addrs.next()
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Server address {} resolved to nothing", self)
+            ))
+    }
+}
+
+impl Default for ServerAddress {
+    fn default() -> Self {
+        Self {
+            host: DEFAULT_SERVER_HOST.to_string(),
+            port: DEFAULT_SERVER_PORT,
+        }
+    }
+}
+
+impl fmt::Display for ServerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub server: ServerAddress,
+    // run without a window
+    pub headless: bool,
+    // stop after this long (headless mode only)
+    pub duration: Option<Duration>,
+    // start an in-process fake server and connect to it instead of `server`
+    #[cfg(feature = "fake-server")]
+    pub fake_server: Option<FakeServerConfig>,
+    // write every received message and every sent input to this file
+    pub record: Option<String>,
+    // replay a session recorded with `record` instead of connecting to a server
+    pub replay: Option<String>,
+    // start with the network conditioner on (it can be toggled in game with F1)
+    pub net_conditioner: bool,
+    pub net_conditions: NetworkConditions,
+    // how far in the past remote entities are drawn: initial value and bounds of adaptation
+    pub interpolation_delay: Duration,
+    pub interpolation_delay_min: Duration,
+    pub interpolation_delay_max: Duration,
+    // used until the server tells its own arena size, topology has to match the server
+    pub arena: Arena,
+}
+
+impl Config {
+    pub fn load() -> Result<Self, String> {
+        let args: Vec<String> = env::args().skip(1).collect();
+        let cli = parse_args(&args)?;
+
+        let mut settings = BTreeMap::new();
+
+        let config_file = cli.get("config").cloned()
+            .or_else(|| env::var(format!("{}CONFIG", ENV_PREFIX)).ok());
+
+        match config_file {
+            Some(path) => settings.extend(read_config_file(&path)?),
+            None => {
+                if Path::new(DEFAULT_CONFIG_FILE).exists() {
+                    settings.extend(read_config_file(DEFAULT_CONFIG_FILE)?);
+                }
+            }
+        }
+
+        for key in KEYS {
+            let var = format!("{}{}", ENV_PREFIX, key.to_uppercase().replace('-', "_"));
+            if let Ok(value) = env::var(var) {
+                settings.insert(key.to_string(), value);
+            }
+        }
+
+        settings.extend(cli.into_iter().filter(|&(ref key, _)| key != "config"));
+
+        Self::from_settings(&settings)
+    }
+
+    fn from_settings(settings: &BTreeMap<String, String>) -> Result<Self, String> {
+        if let Some(key) = settings.keys().find(|key| !KEYS.contains(&key.as_str())) {
+            return Err(format!("Unknown setting '{}'", key));
+        }
+
+        let server = match settings.get("server") {
+            Some(server) => ServerAddress::parse(server)?,
+            None => ServerAddress::default(),
+        };
+
+        let headless = match settings.get("headless") {
+            Some(headless) => parse_bool("headless", headless)?,
+            None => false,
+        };
+
+        let duration = match settings.get("duration") {
+            Some(secs) => Some(Duration::from_secs(parse_number("duration", secs)?)),
+            None => None,
+        };
+
+        let topology = match settings.get("arena-topology").map(String::as_str) {
+            Some("bounce") | None => Topology::Bounce,
+            Some("wrap") => Topology::Wrap,
+            Some(other) => return Err(format!(
+                "Expected 'bounce' or 'wrap' for 'arena-topology', got '{}'", other
+            )),
+        };
+
+        let arena_width: f32 = match settings.get("arena-width") {
+            Some(width) => parse_number("arena-width", width)?,
+            None => constant::window::WIDTH as f32,
+        };
+        let arena_height: f32 = match settings.get("arena-height") {
+            Some(height) => parse_number("arena-height", height)?,
+            None => constant::window::HEIGHT as f32,
+        };
+
+        if !(arena_width > 0.0 && arena_height > 0.0) {
+            return Err(format!("Arena size must be positive, got {}x{}", arena_width, arena_height));
+        }
+
+        let arena = Arena::new(arena_width, arena_height, topology);
+
+        let fake_server_enabled = match settings.get("fake-server") {
+            Some(enabled) => parse_bool("fake-server", enabled)?,
+            None => false,
+        };
+
+        #[cfg(feature = "fake-server")]
+        let fake_server = if fake_server_enabled {
+            Some(fake_server_config(settings, &arena)?)
+        } else {
+            None
+        };
+
+        #[cfg(not(feature = "fake-server"))]
+        {
+            if fake_server_enabled {
+                return Err("'fake-server' needs a client built with `--features fake-server`".to_string());
+            }
+        }
+
+        let net_conditioner = match settings.get("net-conditioner") {
+            Some(enabled) => parse_bool("net-conditioner", enabled)?,
+            None => false,
+        };
+
+        let defaults = NetworkConditions::default();
+        let net_conditions = NetworkConditions {
+            latency: match settings.get("net-latency") {
+                Some(ms) => Duration::from_millis(parse_number("net-latency", ms)?),
+                None => defaults.latency,
+            },
+            jitter: match settings.get("net-jitter") {
+                Some(ms) => Duration::from_millis(parse_number("net-jitter", ms)?),
+                None => defaults.jitter,
+            },
+            jitter_distribution: match settings.get("net-jitter-distribution").map(String::as_str) {
+                Some("uniform") => JitterDistribution::Uniform,
+                Some("normal") => JitterDistribution::Normal,
+                Some(other) => return Err(format!(
+                    "Expected 'uniform' or 'normal' for 'net-jitter-distribution', got '{}'", other
+                )),
+                None => defaults.jitter_distribution,
+            },
+            loss: parse_percent_or(settings, "net-loss", defaults.loss)?,
+            duplication: parse_percent_or(settings, "net-duplication", defaults.duplication)?,
+            reordering: parse_percent_or(settings, "net-reordering", defaults.reordering)?,
+        };
+
+        let interpolation_delay = parse_millis_or(settings, "interp-delay", DEFAULT_INTERPOLATION_DELAY_MS)?;
+        let interpolation_delay_min = parse_millis_or(settings, "interp-delay-min", DEFAULT_INTERPOLATION_DELAY_MIN_MS)?;
+        let interpolation_delay_max = parse_millis_or(settings, "interp-delay-max", DEFAULT_INTERPOLATION_DELAY_MAX_MS)?;
+
+        if interpolation_delay_min > interpolation_delay_max {
+            return Err("'interp-delay-min' must not be greater than 'interp-delay-max'".to_string());
+        }
+
+        Ok(Self {
+            server,
+            headless,
+            duration,
+            #[cfg(feature = "fake-server")]
+            fake_server,
+            record: settings.get("record").cloned(),
+            replay: settings.get("replay").cloned(),
+            net_conditioner,
+            net_conditions,
+            interpolation_delay,
+            interpolation_delay_min,
+            interpolation_delay_max,
+            arena,
+        })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::from_settings(&BTreeMap::new())
+            .expect("Default settings are invalid")
+    }
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(format!("Expected boolean for '{}', got '{}'", key, value)),
+    }
+}
+
+fn parse_number<T>(key: &str, value: &str) -> Result<T, String>
+    where T: str::FromStr, T::Err: fmt::Display
+{
+    value.parse::<T>()
+        .map_err(|err| format!("Invalid value '{}' for '{}': {}", value, key, err))
+}
+
+fn parse_millis_or(settings: &BTreeMap<String, String>, key: &str, default: u64) -> Result<Duration, String> {
+    let ms = match settings.get(key) {
+        Some(ms) => parse_number(key, ms)?,
+        None => default,
+    };
+
+    Ok(Duration::from_millis(ms))
+}
+
+#[cfg(feature = "fake-server")]
+fn parse_percent(settings: &BTreeMap<String, String>, key: &str) -> Result<f32, String> {
+    parse_percent_or(settings, key, 0.0)
+}
+
+// `default` is a fraction, not a percentage
+fn parse_percent_or(settings: &BTreeMap<String, String>, key: &str, default: f32) -> Result<f32, String> {
+    let percent: f32 = match settings.get(key) {
+        Some(percent) => parse_number(key, percent)?,
+        None => return Ok(default),
+    };
+
+    if percent < 0.0 || percent > 100.0 {
+        return Err(format!("'{}' must be a percentage between 0 and 100, got {}", key, percent));
+    }
+
+    Ok(percent / 100.0)
+}
+
+fn parse_args(args: &[String]) -> Result<BTreeMap<String, String>, String> {
+    let mut parsed = BTreeMap::new();
+    let mut args = args.iter().peekable();
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            return Err(format!("Unexpected argument '{}'", arg));
+        }
+
+        let arg = &arg[2..];
+        let (key, value) = match arg.find('=') {
+            Some(idx) => (arg[..idx].to_string(), arg[idx + 1..].to_string()),
+            None => {
+                let has_value = args.peek()
+                    .map(|next| !next.starts_with("--"))
+                    .unwrap_or(false);
+
+                let value = if has_value {
+                    args.next().expect("Checked above").clone()
+                } else {
+                    // bare flag
+                    "true".to_string()
+                };
+
+                (arg.to_string(), value)
+            }
+        };
+
+        parsed.insert(key, value);
+    }
+
+    Ok(parsed)
+}
+
+fn read_config_file(path: &str) -> Result<BTreeMap<String, String>, String> {
+    let file = File::open(path)
+        .map_err(|err| format!("Failed to open config file '{}': {}", path, err))?;
+
+    let mut settings = BTreeMap::new();
+
+    for (line_num, line) in BufReader::new(file).lines().enumerate() {
+        let line = line
+            .map_err(|err| format!("Failed to read config file '{}': {}", path, err))?;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let idx = line.find('=')
+            .ok_or_else(|| format!("{}:{}: expected 'key = value'", path, line_num + 1))?;
+
+        let key = line[..idx].trim().to_string();
+        let value = line[idx + 1..].trim().trim_matches('"').to_string();
+
+        settings.insert(key, value);
+    }
+
+    Ok(settings)
+}
+
+#[cfg(feature = "fake-server")]
+fn fake_server_config(settings: &BTreeMap<String, String>, arena: &Arena) -> Result<FakeServerConfig, String> {
+    let link = LinkConditions {
+        loss: parse_percent(settings, "fake-loss")?,
+        reorder: parse_percent(settings, "fake-reorder")?,
+        delay: match settings.get("fake-delay") {
+            Some(ms) => Duration::from_millis(parse_number("fake-delay", ms)?),
+            None => Duration::from_millis(0),
+        },
+    };
+
+    let asteroids = match settings.get("fake-asteroids") {
+        Some(count) => parse_number("fake-asteroids", count)?,
+        None => 5,
+    };
+
+    let script = match settings.get("fake-script") {
+        Some(path) => Some(read_script_file(path)?),
+        None => None,
+    };
+
+    Ok(FakeServerConfig { link, asteroids, arena: arena.clone(), script })
+}
+
+#[cfg(feature = "fake-server")]
+fn read_script_file(path: &str) -> Result<Vec<fake_server::ScriptStep>, String> {
+    let mut text = String::new();
+
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut text))
+        .map_err(|err| format!("Failed to read fake server script '{}': {}", path, err))?;
+
+    fake_server::parse_script(&text)
+        .map_err(|err| format!("{}: {}", path, err))
+}
@@ -15,6 +15,13 @@ pub mod colors {
         a: 127.0 / 255.0
     };
 
+    pub const WHITE: Color = Color {
+        r: 1.0,
+        g: 1.0,
+        b: 1.0,
+        a: 200.0 / 255.0,
+    };
+
     pub const GREEN: Color = Color {
         r: 162.0 / 255.0,
         g: 215.0 / 255.0,
@@ -23,9 +30,11 @@ pub mod colors {
     };
 }
 
-// TODO: move to server
-//pub const PLAYER_LIFE: f32 = 3.0;
-//pub const ROCK_LIFE: f32 = 2.0;
+// used when the server omits life in entity messages
+pub mod defaults {
+    pub const PLAYER_LIFE: f32 = 3.0;
+    pub const ASTEROID_LIFE: f32 = 2.0;
+}
 
 pub const FIRE_TIMEOUT: f32 = 0.5;
 
@@ -33,6 +42,19 @@ pub mod shot {
     pub const TTL: f32 = 2.0;
     pub const SPEED: f32 = 200.0;
     pub const SIZE: f32 = 6.0;
+
+    // a server shot is taken for a predicted one if it was fired at most this long ago,
+    pub const MATCH_MAX_AGE: f32 = 1.0;
+    // this close to where the predicted one was fired
+    pub const MATCH_MAX_DISTANCE: f32 = 40.0;
+    // and in nearly the same direction (cosine of the angle between them)
+    pub const MATCH_MIN_COS: f32 = 0.95;
+}
+
+pub mod smoothing {
+    // visual offsets shrink e times over this many seconds
+    pub const VISUAL_OFFSET_DECAY_TIME: f32 = 0.1;
+    pub const VISUAL_OFFSET_EPSILON: f32 = 0.1;
 }
 
 
@@ -43,8 +65,72 @@ pub mod physics {
     pub const PLAYER_DECELERATION: f32 = 10.0;
 }
 
+pub mod reconciliation {
+    // prediction errors above this are logged
+    pub const LOG_THRESHOLD: f32 = 5.0;
+    pub const ERROR_SMOOTHING: f32 = 0.1;
+    // corrections stay visible in the debug overlay this long
+    pub const SHOW_CORRECTION_MS: u64 = 1000;
+}
+
+pub mod collision {
+    // side of a spatial hash cell, about the size of an asteroid
+    pub const CELL_SIZE: f32 = 64.0;
+    // bodies are looked up in at most this many cells across, whatever their size
+    pub const MAX_CELLS_ACROSS: f32 = 16.0;
+    // on top of rtt and interpolation delay, before an unconfirmed contact is rolled back
+    pub const CONFIRM_MARGIN_MS: u64 = 100;
+}
+
+pub mod interpolation {
+    // how far a remote entity may be moved past its last known position
+    pub const MAX_EXTRAPOLATION_MS: u64 = 250;
+    // weight of a new observation in the delay's running averages, as in RFC 3550
+    pub const DELAY_SMOOTHING: f32 = 1.0 / 16.0;
+    // how fast the delay follows its target, per server update
+    pub const DELAY_ADJUSTMENT_RATE: f32 = 0.1;
+    // jitter is covered this many times over
+    pub const JITTER_MARGIN: f32 = 3.0;
+}
+
+pub mod network {
+    // reconnect delay doubles with every attempt, starting at base
+    pub const RECONNECT_BASE_DELAY_MS: u64 = 500;
+    pub const RECONNECT_MAX_DELAY_MS: u64 = 8_000;
+    pub const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+}
+
+pub mod camera {
+    // how fast the camera catches up with the player, 1/s
+    pub const STIFFNESS: f32 = 5.0;
+    pub const MIN_ZOOM: f32 = 0.25;
+    pub const MAX_ZOOM: f32 = 4.0;
+    // one key press zooms by this factor
+    pub const ZOOM_STEP: f32 = 1.25;
+}
+
+pub mod window {
+    pub const WIDTH: u32 = 800;
+    pub const HEIGHT: u32 = 600;
+}
+
 pub mod hud {
     pub const HEALTH_BAR_SIZE: f32 = 30.0;
     pub const STATIC_HEALTH_BAR_LINE_WIDTH: f32 = 4.0;
     pub const STICKY_HEALTH_BAR_HEIGHT: f32 = 3.0;
+    pub const ARENA_BORDER_WIDTH: f32 = 1.0;
+    pub const MINIMAP_WIDTH: f32 = 160.0;
+    pub const MINIMAP_HEIGHT: f32 = 120.0;
+    pub const MINIMAP_LINE_WIDTH: f32 = 1.0;
+    pub const MINIMAP_MIN_DOT_RADIUS: f32 = 1.5;
+    pub const MINIMAP_ARROW_SIZE: f32 = 12.0;
+    // sprites fade out during this last part of their time to live
+    pub const FADE_OUT_FRACTION: f32 = 0.25;
+    // part of max life the recent damage trail shrinks by per second
+    pub const DAMAGE_TRAIL_SPEED: f32 = 0.5;
+    pub const HIT_FLASH_DURATION: f32 = 0.15;
+    // how much green and blue are taken out of a sprite at the start of a hit flash
+    pub const HIT_FLASH_STRENGTH: f32 = 0.8;
+    pub const DAMAGE_NUMBER_SPEED: f32 = 40.0;
+    pub const DAMAGE_NUMBER_TTL: f32 = 0.8;
 }
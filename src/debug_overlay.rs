@@ -0,0 +1,122 @@
+use ggez::{
+    Context,
+    GameResult,
+    graphics,
+};
+use specs::World;
+
+use components;
+use constant::{
+    colors,
+    reconciliation,
+};
+use resources;
+use util;
+
+
+const MARGIN: f32 = 10.0;
+const CORRECTION_LINE_WIDTH: f32 = 2.0;
+
+
+/// Networking internals drawn in the top left corner, toggled with F2.
+pub fn draw(ctx: &mut Context, world: &World, font: &graphics::Font) -> GameResult<()> {
+    use specs::Join;
+
+    let mut lines = Vec::new();
+
+    {
+        let server_clock = world.read_resource::<resources::ServerClock>();
+        lines.push(format!(
+            "rtt: {} ms, server clock offset: {} ms",
+            server_clock.rtt(), server_clock.compensation()
+        ));
+    }
+
+    {
+        let corrections = world.read_resource::<resources::Corrections>();
+        lines.push(format!(
+            "prediction error: last {:.1}, mean {:.1}, max {:.1} ({} corrections)",
+            corrections.last.as_ref().map(|correction| correction.error).unwrap_or(0.0),
+            corrections.mean_error, corrections.max_error, corrections.count
+        ));
+    }
+
+    {
+        let interpolation_delay = world.read_resource::<resources::InterpolationDelay>();
+        lines.push(format!(
+            "interpolation delay: {} ms (jitter {:.1} ms)",
+            interpolation_delay.current(), interpolation_delay.jitter()
+        ));
+    }
+
+    {
+        let diagnostics = world.read_resource::<resources::Diagnostics>();
+        lines.push(format!(
+            "dropped from server: {} malformed packets, {} invalid messages, {} updates of unknown entities",
+            diagnostics.malformed_packets, diagnostics.protocol_violations, diagnostics.unknown_entity_updates
+        ));
+    }
+
+    {
+        let contacts = world.read_resource::<resources::PredictedContacts>();
+        lines.push(format!(
+            "predicted contacts: {} pending, {} confirmed, {} rolled back",
+            contacts.pending(), contacts.confirmed, contacts.rolled_back
+        ));
+    }
+
+    {
+        let interp_buffers = world.read::<components::InterpolationBuffer>();
+        let depths: Vec<usize> = (&interp_buffers, ).join()
+            .map(|(interp_buf, )| interp_buf.len())
+            .collect();
+
+        match (depths.iter().min(), depths.iter().max()) {
+            (Some(min), Some(max)) => lines.push(format!(
+                "interpolation buffers: {} entities, {}..{} samples", depths.len(), min, max
+            )),
+            _ => lines.push("interpolation buffers: none".to_string()),
+        }
+    }
+
+    draw_last_correction(ctx, world)?;
+
+    let mut y = MARGIN;
+    for line in lines {
+        let text = graphics::Text::new(ctx, &line, font)?;
+        graphics::draw(ctx, &text, graphics::Point2::new(MARGIN, y), 0.0)?;
+        y += text.height() as f32;
+    }
+
+    Ok(())
+}
+
+// line from where the local player was predicted to where the correction put it, fading out
+fn draw_last_correction(ctx: &mut Context, world: &World) -> GameResult<()> {
+    let corrections = world.read_resource::<resources::Corrections>();
+    let camera = world.read_resource::<resources::Camera>();
+
+    let correction = match corrections.last {
+        Some(ref correction) => correction,
+        None => return Ok(()),
+    };
+
+    let age = util::duration_to_millis(correction.at.elapsed());
+    if age >= reconciliation::SHOW_CORRECTION_MS {
+        return Ok(());
+    }
+
+    let mut color = colors::RED;
+    color.a *= 1.0 - age as f32 / reconciliation::SHOW_CORRECTION_MS as f32;
+
+    let old_color = graphics::get_color(ctx);
+    graphics::set_color(ctx, color)?;
+    graphics::line(
+        ctx,
+        &[camera.world_to_screen(correction.from), camera.world_to_screen(correction.to)],
+        CORRECTION_LINE_WIDTH
+    )?;
+    graphics::set_color(ctx, old_color)?;
+
+    Ok(())
+}
@@ -0,0 +1,918 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::net::{
+    SocketAddr,
+    UdpSocket,
+};
+#[cfg(feature = "fake-server")]
+use std::sync::atomic::{
+    AtomicBool,
+    Ordering,
+};
+use std::str;
+#[cfg(feature = "fake-server")]
+use std::sync::Arc;
+#[cfg(feature = "fake-server")]
+use std::thread;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use rand::{
+    self,
+    Rng,
+};
+
+use ggez::graphics::{
+    Point2,
+    Vector2,
+};
+
+use components::Body;
+use constant;
+use proto::{
+    astero,
+    mmob,
+};
+use resources::Arena;
+use util::{
+    self,
+    cur_time_in_millis,
+};
+
+
+use prost::Message;
+
+
+#[cfg(feature = "fake-server")]
+const RECV_TIMEOUT_MS: u64 = 5;
+const UPDATE_INTERVAL_MS: u64 = 50;
+const HEARTBEAT_INTERVAL_MS: u64 = 1000;
+// every `ASTEROID_RESPAWN_INTERVAL_MS` the oldest asteroid is destroyed and a new one is created
+const ASTEROID_RESPAWN_INTERVAL_MS: u64 = 5000;
+
+const PLAYER_LIFE: f32 = 3.0;
+const PLAYER_SIZE: f32 = 30.0;
+const PLAYER_ROTATION_SPEED: f32 = 3.0;
+const ASTEROID_LIFE: f32 = 2.0;
+const ASTEROID_SIZE: f32 = 40.0;
+const ASTEROID_MAX_SPEED: f32 = 50.0;
+
+
+/// Degrades the link between the fake server and its clients.
+/// Applied to every datagram in both directions.
+#[derive(Debug, Clone, Default)]
+pub struct LinkConditions {
+    // fraction of datagrams dropped, 0.0..1.0
+    pub loss: f32,
+    // fraction of outgoing datagrams held back so that later ones overtake them, 0.0..1.0
+    pub reorder: f32,
+    // one-way delay of outgoing datagrams
+    pub delay: Duration,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct FakeServerConfig {
+    pub link: LinkConditions,
+    pub asteroids: u32,
+    pub arena: Arena,
+    // replaces the simulated world if given
+    pub script: Option<Vec<ScriptStep>>,
+}
+
+
+/// What a script step sends.
+#[derive(Debug, Clone)]
+pub enum ScriptAction {
+    // Create of an asteroid with id
+    Asteroid(u32, Body),
+    // List with a single asteroid update: id, body and life
+    Update(u32, Body, f32),
+    // List with an update of the receiving client's own ship and its last handled input
+    Player(Body, u32),
+    Destroy(u32),
+}
+
+
+/// Sent to every client once `after` has passed since it joined.
+#[derive(Debug, Clone)]
+pub struct ScriptStep {
+    pub after: Duration,
+    pub action: ScriptAction,
+}
+
+
+/// Parses a script, one step per line, `#` starts a comment:
+///
+///     <ms> asteroid <id> <x> <y> <vx> <vy>
+///     <ms> update <id> <x> <y> <vx> <vy> <life>
+///     <ms> player <x> <y> <vx> <vy> <last handled input>
+///     <ms> destroy <id>
+///
+/// `<ms>` is the time since the client joined. Steps are played in time order.
+pub fn parse_script(text: &str) -> Result<Vec<ScriptStep>, String> {
+    let mut steps = Vec::new();
+
+    for (line_num, line) in text.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let step = parse_step(line)
+            .map_err(|err| format!("line {}: {}", line_num + 1, err))?;
+        steps.push(step);
+    }
+
+    // stable, steps at the same time keep their order
+    steps.sort_by_key(|step| step.after);
+
+    Ok(steps)
+}
+
+fn parse_step(line: &str) -> Result<ScriptStep, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    let body = |first: usize, size: f32| -> Result<Body, String> {
+        Ok(Body {
+            pos: Point2::new(arg(&words, first)?, arg(&words, first + 1)?),
+            vel: Vector2::new(arg(&words, first + 2)?, arg(&words, first + 3)?),
+            size,
+            ..Body::default()
+        })
+    };
+
+    let (action, len) = match words.get(1).cloned() {
+        Some("asteroid") => (ScriptAction::Asteroid(arg(&words, 2)?, body(3, ASTEROID_SIZE)?), 7),
+        Some("update") => (ScriptAction::Update(arg(&words, 2)?, body(3, ASTEROID_SIZE)?, arg(&words, 7)?), 8),
+        Some("player") => (ScriptAction::Player(body(2, PLAYER_SIZE)?, arg(&words, 6)?), 7),
+        Some("destroy") => (ScriptAction::Destroy(arg(&words, 2)?), 3),
+        Some(other) => return Err(format!("unknown step '{}'", other)),
+        None => return Err(format!("missing step after '{}'", line)),
+    };
+
+    if words.len() > len {
+        return Err(format!("unexpected '{}'", words[len]));
+    }
+
+    Ok(ScriptStep {
+        after: Duration::from_millis(arg(&words, 0)?),
+        action,
+    })
+}
+
+fn arg<T>(words: &[&str], idx: usize) -> Result<T, String>
+    where T: str::FromStr, T::Err: fmt::Display
+{
+    let word = words.get(idx)
+        .ok_or_else(|| format!("missing argument {}", idx))?;
+
+    word.parse::<T>()
+        .map_err(|err| format!("invalid argument '{}': {}", word, err))
+}
+
+
+/// Datagrams to and from the clients of the fake server.
+/// A UDP socket when running, an in-memory one in tests.
+trait Socket {
+    fn recv_from(&self, buf: &mut [u8]) -> Option<(usize, SocketAddr)>;
+    fn send_to(&self, bytes: &[u8], to: SocketAddr) -> io::Result<()>;
+}
+
+impl Socket for UdpSocket {
+    // nothing within the read timeout
+    fn recv_from(&self, buf: &mut [u8]) -> Option<(usize, SocketAddr)> {
+        UdpSocket::recv_from(self, buf).ok()
+    }
+
+    fn send_to(&self, bytes: &[u8], to: SocketAddr) -> io::Result<()> {
+        UdpSocket::send_to(self, bytes, &to).map(|_| ())
+    }
+}
+
+
+/// Local stand-in for the game server. Speaks the same `mmob` / `astero` envelopes
+/// over UDP: answers JoinGame with JoinAck, echoes LatencyMeasure with `server_timestamp`,
+/// sends Heartbeats and runs a small simulated world of drifting asteroids
+/// or plays a script of Create, Update and Destroy messages instead.
+#[cfg(feature = "fake-server")]
+pub struct FakeServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread_handle: Option<thread::JoinHandle<()>>,
+}
+
+#[cfg(feature = "fake-server")]
+impl FakeServer {
+    pub fn start(bind: SocketAddr, config: FakeServerConfig) -> io::Result<Self> {
+        let socket = UdpSocket::bind(&bind)?;
+        socket.set_read_timeout(Some(Duration::from_millis(RECV_TIMEOUT_MS)))?;
+
+        let addr = socket.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_handle = {
+            let stop = stop.clone();
+
+            thread::spawn(move || {
+                let mut world = World::new(Box::new(socket), config, Instant::now());
+
+                while !stop.load(Ordering::SeqCst) {
+                    world.tick(Instant::now());
+                }
+            })
+        };
+
+        Ok(Self {
+            addr,
+            stop,
+            thread_handle: Some(thread_handle),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+
+        if let Some(thread_handle) = self.thread_handle.take() {
+            thread_handle.join()
+                .expect("Failed to join fake server thread");
+        }
+    }
+}
+
+#[cfg(feature = "fake-server")]
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+
+/// Builds a protobuf message by filling in a default one.
+fn message<T: Default, F: FnOnce(&mut T)>(init: F) -> T {
+    let mut msg = T::default();
+    init(&mut msg);
+
+    msg
+}
+
+
+struct Player {
+    id: u32,
+    nickname: String,
+    body: Body,
+    turn: i32,
+    accel: i32,
+    fire: bool,
+    fire_timeout: f32,
+    last_handled_input: u32,
+    shot_confirmed: bool,
+    joined_at: Instant,
+    // next script step to send
+    script_pos: usize,
+}
+
+struct Asteroid {
+    id: u32,
+    body: Body,
+}
+
+struct Outgoing {
+    deliver_at: Instant,
+    to: SocketAddr,
+    bytes: Vec<u8>,
+}
+
+
+struct World {
+    socket: Box<Socket>,
+    config: FakeServerConfig,
+    arena: Arena,
+    // time of the current tick
+    now: Instant,
+
+    players: HashMap<SocketAddr, Player>,
+    asteroids: Vec<Asteroid>,
+    next_id: u32,
+
+    outgoing: Vec<Outgoing>,
+
+    last_step: Instant,
+    time_acc: f32,
+    last_update: Instant,
+    last_heartbeat: Instant,
+    last_respawn: Instant,
+}
+
+impl World {
+    fn new(socket: Box<Socket>, config: FakeServerConfig, now: Instant) -> Self {
+        let mut world = Self {
+            socket,
+            arena: config.arena.clone(),
+            config,
+            now,
+
+            players: HashMap::new(),
+            asteroids: Vec::new(),
+            next_id: 1,
+
+            outgoing: Vec::new(),
+
+            last_step: now,
+            time_acc: 0.0,
+            last_update: now,
+            last_heartbeat: now,
+            last_respawn: now,
+        };
+
+        if world.config.script.is_none() {
+            for _ in 0..world.config.asteroids {
+                world.spawn_asteroid();
+            }
+        }
+
+        world
+    }
+
+    fn tick(&mut self, now: Instant) {
+        self.now = now;
+        self.receive();
+
+        if self.config.script.is_some() {
+            // the script is the whole world
+            self.play_script(now);
+        } else {
+            self.simulate(now);
+        }
+
+        if now - self.last_heartbeat >= Duration::from_millis(HEARTBEAT_INTERVAL_MS) {
+            self.last_heartbeat = now;
+            self.broadcast_mmob(mmob::server::Msg::Heartbeat(mmob::Heartbeat {}));
+        }
+
+        self.flush();
+    }
+
+    fn simulate(&mut self, now: Instant) {
+        self.time_acc += util::duration_to_secs(now - self.last_step);
+        self.last_step = now;
+
+        while self.time_acc > constant::physics::DELTA_TIME {
+            self.step(constant::physics::DELTA_TIME);
+            self.time_acc -= constant::physics::DELTA_TIME;
+        }
+
+        if now - self.last_update >= Duration::from_millis(UPDATE_INTERVAL_MS) {
+            self.last_update = now;
+            self.send_updates();
+        }
+
+        if now - self.last_respawn >= Duration::from_millis(ASTEROID_RESPAWN_INTERVAL_MS) {
+            self.last_respawn = now;
+
+            if !self.asteroids.is_empty() {
+                let oldest = self.asteroids.remove(0);
+                self.broadcast(astero::server::Msg::Destroy(message(|destroy| destroy.id = oldest.id)));
+            }
+
+            let create = self.spawn_asteroid();
+            self.broadcast(create);
+        }
+    }
+
+    // sends every client the steps that became due since it joined
+    fn play_script(&mut self, now: Instant) {
+        let mut due = Vec::new();
+
+        if let Some(ref script) = self.config.script {
+            for (&addr, player) in &mut self.players {
+                while let Some(step) = script.get(player.script_pos) {
+                    if now - player.joined_at < step.after {
+                        break;
+                    }
+
+                    player.script_pos += 1;
+                    due.push((addr, script_msg(&step.action, player)));
+                }
+            }
+        }
+
+        for (to, msg) in due {
+            self.send(to, msg);
+        }
+    }
+
+    fn receive(&mut self) {
+        let mut buf = [0; 65_536];
+
+        loop {
+            let (len, from) = match self.socket.recv_from(&mut buf) {
+                Some(received) => received,
+                None => return,
+            };
+
+            if self.lost() {
+                continue;
+            }
+
+            let msg = match mmob::Client::decode(&buf[..len]) {
+                Ok(mmob::Client { msg: Some(msg), .. }) => msg,
+                _ => {
+                    println!("[fake server] Dropping malformed packet from {}", from);
+                    continue;
+                }
+            };
+
+            self.handle(from, msg);
+        }
+    }
+
+    fn handle(&mut self, from: SocketAddr, msg: mmob::client::Msg) {
+        match msg {
+            mmob::client::Msg::Join(join) => {
+                let nickname = join.payload
+                    .and_then(|payload| astero::JoinPayload::decode(payload).ok())
+                    .map(|payload| payload.nickname)
+                    .unwrap_or_else(|| "anonymous".to_string());
+
+                self.join(from, nickname);
+            }
+
+            mmob::client::Msg::Leave(..) => {
+                if let Some(player) = self.players.remove(&from) {
+                    self.broadcast(astero::server::Msg::Destroy(message(|destroy| destroy.id = player.id)));
+                }
+            }
+
+            mmob::client::Msg::Heartbeat(..) => {}
+
+            mmob::client::Msg::LatencyMeasure(mut measure) => {
+                measure.server_timestamp = Some(cur_time_in_millis());
+                self.send_mmob(from, mmob::server::Msg::LatencyMeasure(measure));
+            }
+
+            mmob::client::Msg::Proxied(proxied) => {
+                let input = match astero::Client::decode(proxied.msg) {
+                    Ok(astero::Client { msg: Some(astero::client::Msg::Input(input)), .. }) => input,
+                    _ => return,
+                };
+
+                if let Some(player) = self.players.get_mut(&from) {
+                    player.turn = input.turn.unwrap_or(player.turn);
+                    player.accel = input.accel.unwrap_or(player.accel);
+                    player.fire = input.fire.unwrap_or(player.fire);
+                    player.last_handled_input = input.sequence_num;
+                }
+            }
+        }
+    }
+
+    fn join(&mut self, from: SocketAddr, nickname: String) {
+        if let Some(player) = self.players.remove(&from) {
+            // rejoin after reconnect: forget the old ship
+            self.broadcast(astero::server::Msg::Destroy(message(|destroy| destroy.id = player.id)));
+        }
+
+        let id = self.next_id();
+        let player = Player {
+            id,
+            nickname,
+            body: Body {
+                rvel: PLAYER_ROTATION_SPEED,
+                size: PLAYER_SIZE,
+                ..Body::default()
+            },
+            turn: 0,
+            accel: 0,
+            fire: false,
+            fire_timeout: 0.0,
+            last_handled_input: 0,
+            shot_confirmed: true,
+            joined_at: self.now,
+            script_pos: 0,
+        };
+
+        let mut payload = Vec::new();
+        player_msg(&player).encode(&mut payload)
+            .expect("Failed to encode player");
+        self.send_mmob(from, mmob::server::Msg::JoinAck(message(|ack| ack.payload = Some(payload))));
+
+        let mut creates: Vec<_> = self.players.values()
+            .map(|other| create_msg(astero::create::Entity::Player(player_msg(other))))
+            .collect();
+        creates.extend(self.asteroids.iter().map(asteroid_create_msg));
+
+        for create in creates {
+            self.send(from, create);
+        }
+
+        self.broadcast(create_msg(astero::create::Entity::Player(player_msg(&player))));
+        self.players.insert(from, player);
+    }
+
+    fn step(&mut self, dt: f32) {
+        let mut shots = Vec::new();
+
+        for player in self.players.values_mut() {
+            player.body.accelerate(
+                dt, player.accel,
+                constant::physics::PLAYER_ACCELERATION,
+                constant::physics::PLAYER_DECELERATION
+            );
+            player.body.update_position(dt);
+            player.body.rotate(dt, player.turn);
+            self.arena.confine(&mut player.body);
+
+            player.fire_timeout -= dt;
+            if player.fire && player.fire_timeout <= 0.0 {
+                player.fire_timeout = constant::FIRE_TIMEOUT;
+                player.shot_confirmed = true;
+
+                let mut shot = player.body.clone();
+                let direction = util::vec_from_angle(shot.rot);
+                shot.vel = direction * constant::shot::SPEED;
+                shot.size = constant::shot::SIZE;
+
+                shots.push(shot);
+            }
+        }
+
+        for asteroid in &mut self.asteroids {
+            asteroid.body.update_position(dt);
+            self.arena.confine(&mut asteroid.body);
+        }
+
+        for shot in shots {
+            self.broadcast(create_msg(astero::create::Entity::Shot(message(|msg: &mut astero::Shot| {
+                msg.body = shot.into();
+                msg.ttl = constant::shot::TTL;
+            }))));
+        }
+    }
+
+    fn send_updates(&mut self) {
+        let mut updates = Vec::new();
+
+        for player in self.players.values() {
+            let entity = astero::update::Entity::Player(message(|update| {
+                update.id = player.id;
+                update.body = player.body.clone().into();
+                update.current_fire_timeout = Some(player.fire_timeout.max(0.0));
+                update.last_handled_input = Some(player.last_handled_input);
+                update.shot_confirmed = Some(player.shot_confirmed);
+            }));
+
+            updates.push((player.id, entity));
+        }
+
+        for asteroid in &self.asteroids {
+            let entity = astero::update::Entity::Asteroid(message(|update| {
+                update.body = asteroid.body.clone().into();
+            }));
+
+            updates.push((asteroid.id, entity));
+        }
+
+        self.broadcast(list_msg(updates));
+    }
+
+    fn spawn_asteroid(&mut self) -> astero::server::Msg {
+        let mut rng = rand::thread_rng();
+
+        // fully inside the arena
+        let max_x = (self.arena.width / 2.0 - ASTEROID_SIZE / 2.0).max(1.0);
+        let max_y = (self.arena.height / 2.0 - ASTEROID_SIZE / 2.0).max(1.0);
+
+        let id = self.next_id();
+        let asteroid = Asteroid {
+            id,
+            body: Body {
+                pos: Point2::new(rng.gen_range(-max_x, max_x), rng.gen_range(-max_y, max_y)),
+                vel: Vector2::new(
+                    rng.gen_range(-ASTEROID_MAX_SPEED, ASTEROID_MAX_SPEED),
+                    rng.gen_range(-ASTEROID_MAX_SPEED, ASTEROID_MAX_SPEED),
+                ),
+                rot: rng.gen_range(0.0, 2.0 * ::std::f32::consts::PI),
+                size: ASTEROID_SIZE,
+                ..Body::default()
+            },
+        };
+
+        let create = asteroid_create_msg(&asteroid);
+        self.asteroids.push(asteroid);
+
+        create
+    }
+
+    fn next_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        id
+    }
+
+    fn lost(&self) -> bool {
+        rand::random::<f32>() < self.config.link.loss
+    }
+
+    fn broadcast(&mut self, msg: astero::server::Msg) {
+        let clients: Vec<_> = self.players.keys().cloned().collect();
+        for client in clients {
+            self.send(client, msg.clone());
+        }
+    }
+
+    fn broadcast_mmob(&mut self, msg: mmob::server::Msg) {
+        let clients: Vec<_> = self.players.keys().cloned().collect();
+        for client in clients {
+            self.send_mmob(client, msg.clone());
+        }
+    }
+
+    fn send(&mut self, to: SocketAddr, msg: astero::server::Msg) {
+        let mut payload = Vec::new();
+        message(|server: &mut astero::Server| server.msg = Some(msg)).encode(&mut payload)
+            .expect("Failed to encode astero message");
+
+        self.send_mmob(to, mmob::server::Msg::Proxied(mmob::Proxied { msg: payload }));
+    }
+
+    fn send_mmob(&mut self, to: SocketAddr, msg: mmob::server::Msg) {
+        if self.lost() {
+            return;
+        }
+
+        let mut bytes = Vec::new();
+        mmob::Server { msg: Some(msg) }.encode(&mut bytes)
+            .expect("Failed to encode mmob message");
+
+        let mut delay = self.config.link.delay;
+        if rand::random::<f32>() < self.config.link.reorder {
+            // hold the datagram back long enough for the next update to overtake it
+            delay += Duration::from_millis(rand::thread_rng().gen_range(1, 2 * UPDATE_INTERVAL_MS));
+        }
+
+        self.outgoing.push(Outgoing {
+            deliver_at: self.now + delay,
+            to,
+            bytes,
+        });
+    }
+
+    fn flush(&mut self) {
+        let now = self.now;
+        let socket = &self.socket;
+
+        self.outgoing.sort_by_key(|outgoing| outgoing.deliver_at);
+        self.outgoing.retain(|outgoing| {
+            if outgoing.deliver_at > now {
+                return true;
+            }
+
+            if let Err(err) = socket.send_to(&outgoing.bytes, outgoing.to) {
+                println!("[fake server] Failed to send to {}: {}", outgoing.to, err);
+            }
+
+            false
+        });
+    }
+}
+
+
+fn player_msg(player: &Player) -> astero::Player {
+    message(|msg: &mut astero::Player| {
+        msg.id = player.id;
+        msg.body = player.body.clone().into();
+        msg.life = Some(PLAYER_LIFE);
+        msg.nickname = Some(player.nickname.clone());
+    })
+}
+
+fn asteroid_create_msg(asteroid: &Asteroid) -> astero::server::Msg {
+    create_msg(astero::create::Entity::Asteroid(message(|msg| {
+        msg.id = asteroid.id;
+        msg.body = asteroid.body.clone().into();
+        msg.life = Some(ASTEROID_LIFE);
+    })))
+}
+
+fn create_msg(entity: astero::create::Entity) -> astero::server::Msg {
+    astero::server::Msg::Create(message(|create| create.entity = Some(entity)))
+}
+
+fn list_msg(entities: Vec<(u32, astero::update::Entity)>) -> astero::server::Msg {
+    let updates: HashMap<_, _> = entities.into_iter()
+        .map(|(id, entity)| (id, message(|update: &mut astero::Update| update.entity = Some(entity))))
+        .collect();
+
+    let timestamp = cur_time_in_millis();
+    astero::server::Msg::List(message(|list| {
+        list.timestamp = timestamp;
+        list.updates = updates;
+    }))
+}
+
+fn script_msg(action: &ScriptAction, player: &Player) -> astero::server::Msg {
+    match *action {
+        ScriptAction::Asteroid(id, ref body) => asteroid_create_msg(&Asteroid {
+            id,
+            body: body.clone(),
+        }),
+        ScriptAction::Update(id, ref body, life) => {
+            let entity = astero::update::Entity::Asteroid(message(|update| {
+                update.body = body.clone().into();
+                update.life = Some(life);
+            }));
+
+            list_msg(vec![(id, entity)])
+        }
+        ScriptAction::Player(ref body, last_handled_input) => {
+            let entity = astero::update::Entity::Player(message(|update| {
+                update.id = player.id;
+                update.body = body.clone().into();
+                update.last_handled_input = Some(last_handled_input);
+                update.shot_confirmed = Some(true);
+            }));
+
+            list_msg(vec![(player.id, entity)])
+        }
+        ScriptAction::Destroy(id) => astero::server::Msg::Destroy(message(|destroy| destroy.id = id)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::net::{
+        IpAddr,
+        Ipv4Addr,
+        SocketAddr,
+    };
+    use std::time::{
+        Duration,
+        Instant,
+    };
+
+    use specs::{
+        self,
+        Join,
+    };
+
+    use components;
+    use config::Config;
+    use constant;
+    use game::Game;
+    use msg::Msg;
+    use resources::{
+        self,
+        Arena,
+        Topology,
+    };
+    use transport::{
+        LoopbackPeer,
+        LoopbackTransport,
+    };
+
+    use super::{
+        FakeServerConfig,
+        LinkConditions,
+        ScriptAction,
+        Socket,
+        World,
+        parse_script,
+    };
+
+
+    const SCRIPT: &str = "
+        # asteroid is created, takes a hit and is destroyed
+        0    asteroid 100 50 50 0 0
+        100  update 100 50 50 0 0 1.5
+        # server puts the ship somewhere else than predicted
+        150  player 120 0 0 0 0
+        400  destroy 100
+    ";
+
+    // simulated time the game gets to reach a state, way past the end of the script
+    const MAX_STEPS: u32 = 200;
+
+
+    // The game's side of a `LoopbackTransport` as seen by the fake server.
+    struct LoopbackSocket(LoopbackPeer);
+
+    fn client_addr() -> SocketAddr {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::localhost()), 1)
+    }
+
+    impl Socket for LoopbackSocket {
+        fn recv_from(&self, buf: &mut [u8]) -> Option<(usize, SocketAddr)> {
+            let bytes = self.0.try_recv()?.to_client_bytes()?;
+            buf[..bytes.len()].copy_from_slice(&bytes);
+
+            Some((bytes.len(), client_addr()))
+        }
+
+        fn send_to(&self, bytes: &[u8], _to: SocketAddr) -> io::Result<()> {
+            self.0.send(Msg::from_bytes(bytes).unwrap_or_else(Msg::Malformed));
+
+            Ok(())
+        }
+    }
+
+    fn start_scripted(script: &str) -> (World, Game<'static, 'static>) {
+        let config = FakeServerConfig {
+            link: LinkConditions::default(),
+            asteroids: 0,
+            arena: Arena::new(800.0, 600.0, Topology::Bounce),
+            script: Some(parse_script(script).expect("Invalid script")),
+        };
+
+        let (transport, peer) = LoopbackTransport::pair();
+        let server = World::new(Box::new(LoopbackSocket(peer)), config, Instant::now());
+        let game = Game::new(Box::new(transport), &Config::default());
+
+        (server, game)
+    }
+
+    // Steps the server and the game together in simulated time until `done` holds,
+    // false if it doesn't within `MAX_STEPS`.
+    fn run_until<F>(server: &mut World, game: &mut Game, done: F) -> bool
+        where F: Fn(&specs::World) -> bool
+    {
+        let step = Duration::from_millis((constant::physics::DELTA_TIME * 1000.0) as u64);
+
+        for _ in 0..MAX_STEPS {
+            let now = server.now + step;
+            server.tick(now);
+            game.update(constant::physics::DELTA_TIME);
+
+            if done(&game.world) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn asteroid(world: &specs::World) -> Option<specs::Entity> {
+        world.read_resource::<resources::NetworkEntityMap>().get_entity(100)
+    }
+
+    #[test]
+    fn script_is_parsed_in_time_order() {
+        let script = parse_script("
+            200 destroy 7
+            # comment
+            100 asteroid 7 1 2 3 4
+        ").expect("Invalid script");
+
+        assert_eq!(script.len(), 2);
+        assert_eq!(script[0].after, Duration::from_millis(100));
+        match script[0].action {
+            ScriptAction::Asteroid(7, ref body) => {
+                assert_eq!((body.pos.x, body.pos.y, body.vel.x, body.vel.y), (1.0, 2.0, 3.0, 4.0));
+            }
+            ref other => panic!("Unexpected {:?}", other),
+        }
+
+        assert!(parse_script("0 destroy").is_err());
+        assert!(parse_script("0 explode 1").is_err());
+        assert!(parse_script("0 destroy 1 2").is_err());
+    }
+
+    #[test]
+    fn scripted_world_reaches_the_game() {
+        let (mut server, mut game) = start_scripted(SCRIPT);
+
+        assert!(run_until(&mut server, &mut game, |world| asteroid(world).is_some()), "asteroid was not created");
+
+        assert!(run_until(&mut server, &mut game, |world| {
+            let lives = world.read::<components::Life>();
+            asteroid(world)
+                .and_then(|entity| lives.get(entity))
+                .map(|life| (life.fraction() - 0.75).abs() < 1e-6)
+                .unwrap_or(false)
+        }), "asteroid life was not updated");
+
+        // nothing pending, the ship ends up exactly where the server put it
+        assert!(run_until(&mut server, &mut game, |world| {
+            let controllables = world.read::<components::Controllable>();
+            let bodies = world.read::<components::Body>();
+            (&controllables, &bodies).join()
+                .any(|(_, body)| (body.pos.x - 120.0).abs() < 1e-3 && body.pos.y.abs() < 1e-3)
+        }), "local player was not reconciled");
+        assert_eq!(game.world.read_resource::<resources::Corrections>().count, 1);
+
+        assert!(run_until(&mut server, &mut game, |world| asteroid(world).is_none()), "asteroid was not destroyed");
+
+        game.stop();
+    }
+}
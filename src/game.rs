@@ -0,0 +1,853 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::Instant;
+
+use ggez::graphics::Vector2;
+
+use specs::{
+    World,
+    DispatcherBuilder,
+    Dispatcher,
+    Entity,
+};
+
+use components;
+use config::Config;
+use constant;
+use msg;
+use proto::{
+    self,
+    astero,
+};
+use resources;
+use systems;
+use transport::Transport;
+use util;
+use validation;
+
+
+pub enum Status {
+    Running,
+    Disconnected,
+}
+
+
+/// Game state that does not depend on a window: ECS world, networking and prediction.
+/// Rendering and keyboard handling live on top of it in `MainState`.
+pub struct Game<'a, 'b> {
+    pub world: World,
+    dispatcher: Dispatcher<'a, 'b>,
+
+    client: resources::Client,
+    pending_inputs: resources::InputBuffer,
+
+    time_acc: f32,
+    last_server_update_timestamp: u64,
+    player_id: i64,
+}
+
+impl<'a, 'b> Game<'a, 'b> {
+    pub fn new(transport: Box<Transport>, config: &Config) -> Self {
+        let mut world = World::new();
+
+        world.add_resource(resources::Input::new());
+        world.add_resource(resources::ServerClock::new());
+        world.add_resource(resources::CurrentSystemRunMode(resources::SystemRunMode::Prediction));
+        world.add_resource(resources::UnconfirmedShotId(None));
+        world.add_resource(resources::Diagnostics::new());
+        world.add_resource(resources::Corrections::new());
+        world.add_resource(resources::DamageEvents(Vec::new()));
+        world.add_resource(resources::PredictedContacts::new());
+        world.add_resource(resources::NetworkEntityMap::new());
+        world.add_resource(config.arena.clone());
+        world.add_resource(resources::InterpolationDelay::new(
+            util::duration_to_millis(config.interpolation_delay),
+            util::duration_to_millis(config.interpolation_delay_min),
+            util::duration_to_millis(config.interpolation_delay_max),
+        ));
+
+        world.register::<components::Sprite>();
+        world.register::<components::Body>();
+        world.register::<components::Nickname>();
+        world.register::<components::Color>();
+        world.register::<components::Life>();
+        world.register::<components::StickyHealthBar>();
+        world.register::<components::StaticHealthBar>();
+        world.register::<components::Minimap>();
+        world.register::<components::Controllable>();
+        world.register::<components::NetworkId>();
+        world.register::<components::TimeToLive>();
+        world.register::<components::Accelerator>();
+        world.register::<components::InterpolationBuffer>();
+        world.register::<components::BlenderBody>();
+        world.register::<components::Cannon>();
+        world.register::<components::ShotNetworkId>();
+        world.register::<components::PredictedShot>();
+        world.register::<components::VisualOffset>();
+        world.register::<components::HitFlash>();
+        world.register::<components::DamageNumber>();
+
+        let dispatcher = DispatcherBuilder::new()
+            .add(systems::KinematicsPrediction, "KinematicsPrediction", &[])
+            .add(systems::KinematicsSimulation, "KinematicsSimulation", &[])
+            .add(systems::Interpolation, "Interpolation", &[])
+            .add(systems::Shooting, "Shooting", &[])
+            .add(systems::VisualOffsetDecay, "VisualOffsetDecay", &[])
+            .add(systems::CollisionPrediction, "CollisionPrediction", &[])
+            .add(systems::DamageEffects, "DamageEffects", &[])
+            .add(systems::TimeToLiveExpiry, "TimeToLiveExpiry", &[])
+            .build();
+
+        let nickname = util::cur_user_name();
+
+        let conditioner = resources::NetworkConditioner::new(config.net_conditions.clone(), config.net_conditioner);
+
+        let mut client = resources::Client::new(transport, conditioner);
+        client.join(nickname);
+
+        Self {
+            world,
+            dispatcher,
+
+            client,
+            pending_inputs: resources::InputBuffer::new(),
+
+            time_acc: 0.0,
+            last_server_update_timestamp: 0,
+            player_id: -1,
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.client.stop();
+    }
+
+    pub fn toggle_network_conditioner(&mut self) {
+        self.client.toggle_network_conditioner();
+    }
+
+    pub fn is_reconnecting(&self) -> bool {
+        match self.client.state() {
+            resources::ConnectionState::Reconnecting { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Forgets everything received from the server.
+    /// After reconnect the world is rebuilt from fresh JoinAck and Create messages.
+    fn reset(&mut self) {
+        use specs::Join;
+
+        let all: Vec<_> = (&*self.world.entities()).join().collect();
+        for entity in all {
+            self.world.delete_entity(entity)
+                .expect("Deleting already deleted entity?!");
+        }
+        self.world.maintain();
+
+        self.world.write_resource::<resources::NetworkEntityMap>().clear();
+        self.world.write_resource::<resources::InterpolationDelay>().reset();
+        self.world.write_resource::<resources::PredictedContacts>().clear();
+        self.world.write_resource::<resources::DamageEvents>().0.clear();
+        *self.world.write_resource::<resources::Corrections>() = resources::Corrections::new();
+
+        {
+            let mut input = self.world.write_resource::<resources::Input>();
+            *input = resources::Input::new();
+        }
+        {
+            let mut unconfirmed_shot_id = self.world.write_resource::<resources::UnconfirmedShotId>();
+            unconfirmed_shot_id.0 = None;
+        }
+
+        self.pending_inputs = resources::InputBuffer::new();
+        self.last_server_update_timestamp = 0;
+        self.player_id = -1;
+    }
+
+    /// Handles network messages and advances the simulation by `frame_time` seconds
+    /// in fixed `physics::DELTA_TIME` steps.
+    pub fn update(&mut self, frame_time: f32) -> Status {
+        while let Some(msg) = self.client.try_recv() {
+            if let Status::Disconnected = self.handle_message(msg) {
+                return Status::Disconnected;
+            }
+        }
+
+        {
+            let mut cur_system_run_mode = self.world.write_resource::<resources::CurrentSystemRunMode>();
+            cur_system_run_mode.0 = resources::SystemRunMode::Prediction;
+        }
+
+        self.time_acc += frame_time;
+
+        while self.time_acc > constant::physics::DELTA_TIME {
+            self.dispatcher.dispatch(&self.world.res);
+            // systems only mark entities for deletion
+            self.world.maintain();
+            self.pending_inputs.increase_update_step();
+            self.time_acc -= constant::physics::DELTA_TIME;
+        }
+
+        Status::Running
+    }
+
+    /// Moves remote entities to their interpolated positions and blends predicted ones
+    /// between the last two fixed steps.
+    pub fn interpolate(&mut self) {
+        {
+            let delay = self.world.read_resource::<resources::InterpolationDelay>().current();
+
+            let mut cur_sys_run_mode = self.world.write_resource::<resources::CurrentSystemRunMode>();
+            cur_sys_run_mode.0 = resources::SystemRunMode::Interpolation(
+                util::cur_time_in_millis().saturating_sub(delay),
+                self.time_acc / constant::physics::DELTA_TIME
+            );
+        }
+
+        self.dispatcher.dispatch(&self.world.res);
+    }
+
+    pub fn update_input(&mut self, cur_input: resources::Input, maybe_update: Option<proto::astero::Input>) {
+        if let Some(update) = maybe_update {
+            self.push_input(cur_input, update, false);
+        }
+    }
+
+    // a replayed input keeps its recorded sequence number, a new one gets the next
+    fn push_input(&mut self, cur_input: resources::Input, mut update: proto::astero::Input, replayed: bool) {
+        use specs::Join;
+
+        let cannons = self.world.read::<components::Cannon>();
+        let bodies = self.world.read::<components::Body>();
+
+        let snapshot = (&cannons, &bodies).join().next()
+            .map(|(_, body)| body.clone());
+        update.sequence_num = if replayed {
+            self.pending_inputs.add_replayed(update.sequence_num, cur_input, snapshot)
+        } else {
+            self.pending_inputs.add(cur_input, snapshot)
+        };
+
+        let maybe_player = (&cannons, &bodies).join().next();
+
+        if let Some((cannon, body)) = maybe_player {
+            if cannon.ready_to_fire() {
+                update.body_then = Some(body.clone().into());
+            }
+
+            {
+                let mut unconfirmed_shot_id = self.world.write_resource::<resources::UnconfirmedShotId>();
+                unconfirmed_shot_id.0 = Some(update.sequence_num);
+            }
+        }
+
+        self.client.send(msg::Msg::ToServer(update.into()));
+    }
+
+    fn handle_message(&mut self, msg: msg::Msg) -> Status {
+        match msg {
+            msg::Msg::JoinAck(cur_player) => {
+                match validation::player(&cur_player) {
+                    Ok(cur_player) => self.create_local_player(cur_player),
+                    Err(violation) => self.protocol_violation(&violation),
+                }
+            },
+
+            msg::Msg::FromServer(msg) => {
+                match msg {
+                    astero::server::Msg::Create(create) => {
+                        match validation::create(&create) {
+                            Ok(create) => self.create_remote_entity(create),
+                            Err(violation) => self.protocol_violation(&violation),
+                        }
+                    }
+                    astero::server::Msg::Destroy(entity_to_destroy) => {
+                        self.destroy_entity(entity_to_destroy.id);
+                    },
+                    astero::server::Msg::List(updates) => {
+                        if updates.timestamp < self.last_server_update_timestamp {
+                            return Status::Running;
+                        }
+                        self.last_server_update_timestamp = updates.timestamp;
+
+                        {
+                            let latency = self.world.read_resource::<resources::ServerClock>().rtt() / 2;
+                            let mut interpolation_delay = self.world.write_resource::<resources::InterpolationDelay>();
+                            interpolation_delay.on_update(updates.timestamp, util::cur_time_in_millis(), latency);
+                        }
+
+                        self.apply_updates(updates.timestamp, &updates.updates);
+                    },
+                }
+            }
+
+            msg::Msg::ServerNotResponding => {
+                println!("Server is not responding! Reconnecting...");
+                self.reset();
+            }
+
+            msg::Msg::ReconnectFailed => {
+                println!("Server is not available! Closing game...");
+                return Status::Disconnected;
+            }
+
+            msg::Msg::Latency(ref measure) => {
+                match validation::server_timestamp(measure.server_timestamp) {
+                    Ok(server_timestamp) => {
+                        let just_synced = {
+                            let mut server_clock = self.world.write_resource::<resources::ServerClock>();
+                            let was_synced = server_clock.is_synced();
+                            server_clock.update(measure.timestamp, server_timestamp);
+
+                            !was_synced && server_clock.is_synced()
+                        };
+
+                        // samples so far are keyed by arrival time, they don't mix with server time
+                        if just_synced {
+                            use specs::Join;
+
+                            for interp_buf in (&mut self.world.write::<components::InterpolationBuffer>()).join() {
+                                interp_buf.clear();
+                            }
+                        }
+                    }
+                    Err(violation) => self.protocol_violation(&violation),
+                }
+            },
+
+            msg::Msg::Malformed(ref err) => {
+                let mut diagnostics = self.world.write_resource::<resources::Diagnostics>();
+                diagnostics.malformed_packet(err);
+            }
+
+            // goes the same way as a key press, with the recorded sequence number
+            // so that the recorded acks of the server match the replayed inputs
+            msg::Msg::ReplayedInput(update) => {
+                let input = {
+                    let mut input = self.world.write_resource::<resources::Input>();
+                    input.apply(&update);
+
+                    input.clone()
+                };
+
+                self.push_input(input, update, true);
+            }
+
+            msg::Msg::Unknown | msg::Msg::JoinGame(..) | msg::Msg::LeaveGame |
+            msg::Msg::Heartbeat | msg::Msg::ToServer(..) => unreachable!(),
+        }
+
+        Status::Running
+    }
+
+    fn create_local_player(&mut self, cur_player: validation::Player) {
+        if !self.check_new_network_id(cur_player.id) {
+            return;
+        }
+
+        self.player_id = i64::from(cur_player.id);
+
+        let entity = self.world.create_entity()
+            .with(cur_player.body)
+            .with(components::BlenderBody::new())
+            .with(components::Accelerator::new(
+                constant::physics::PLAYER_ACCELERATION,
+                constant::physics::PLAYER_DECELERATION
+            ))
+            .with(components::Cannon::new(constant::FIRE_TIMEOUT))
+            .with(components::Color(constant::colors::GREEN))
+            .with(components::Life::new(cur_player.life))
+            .with(components::StaticHealthBar::new(
+                10 as f32,
+                constant::window::HEIGHT as f32 - constant::hud::HEALTH_BAR_SIZE - 5.0,
+                (constant::window::WIDTH / 2) as f32,
+                constant::hud::HEALTH_BAR_SIZE))
+            .with(components::StickyHealthBar {})
+            .with(components::Minimap::new(
+                constant::window::WIDTH as f32 - constant::hud::MINIMAP_WIDTH - 10.0,
+                constant::window::HEIGHT as f32 - constant::hud::MINIMAP_HEIGHT - 10.0,
+                constant::hud::MINIMAP_WIDTH,
+                constant::hud::MINIMAP_HEIGHT))
+            .with(components::Sprite(resources::SpriteKind::Player))
+            .with(components::Nickname::new(&cur_player.nickname))
+            .with(components::Controllable {})
+            .with(components::NetworkId(cur_player.id))
+            .build();
+
+        self.world.write_resource::<resources::NetworkEntityMap>().insert(cur_player.id, entity);
+    }
+
+    fn create_remote_entity(&mut self, create: validation::Create) {
+        match create {
+            validation::Create::Player(other) => {
+                if !self.check_new_network_id(other.id) {
+                    return;
+                }
+
+                let entity = self.world.create_entity()
+                    .with(other.body)
+                    .with(components::Color(constant::colors::RED))
+                    .with(components::Life::new(other.life))
+                    .with(components::StickyHealthBar {})
+                    .with(components::Sprite(resources::SpriteKind::Player))
+                    .with(components::Nickname::new(&other.nickname))
+                    .with(components::NetworkId(other.id))
+                    .with(components::InterpolationBuffer::new())
+                    .build();
+
+                self.world.write_resource::<resources::NetworkEntityMap>().insert(other.id, entity);
+            }
+            validation::Create::Asteroid(asteroid) => {
+                if !self.check_new_network_id(asteroid.id) {
+                    return;
+                }
+
+                let entity = self.world.create_entity()
+                    .with(asteroid.body)
+                    .with(components::Color(constant::colors::RED))
+                    .with(components::Life::new(asteroid.life))
+                    .with(components::StickyHealthBar {})
+                    .with(components::Sprite(resources::SpriteKind::Asteroid))
+                    .with(components::NetworkId(asteroid.id))
+                    .with(components::InterpolationBuffer::new())
+                    .build();
+
+                self.world.write_resource::<resources::NetworkEntityMap>().insert(asteroid.id, entity);
+            }
+            validation::Create::Shot(shot) => {
+                if self.adopt_predicted_shot(&shot) {
+                    return;
+                }
+
+                self.world.create_entity()
+                    .with(shot.body)
+                    .with(components::Sprite(resources::SpriteKind::Shot))
+                    .with(components::TimeToLive::new(shot.ttl))
+                    .build();
+            }
+        }
+    }
+
+    /// Finds the predicted shot the server just created and moves it onto the server trajectory.
+    /// The jump is hidden by a visual offset that fades out.
+    fn adopt_predicted_shot(&mut self, shot: &validation::Shot) -> bool {
+        use specs::Join;
+
+        let entities = self.world.entities();
+        let mut predicted_shots = self.world.write::<components::PredictedShot>();
+        let mut bodies = self.world.write::<components::Body>();
+        let mut ttls = self.world.write::<components::TimeToLive>();
+        let mut offsets = self.world.write::<components::VisualOffset>();
+        let mut shot_network_ids = self.world.write::<components::ShotNetworkId>();
+        let arena = self.world.read_resource::<resources::Arena>();
+
+        let server_dir = normalized(shot.body.vel);
+
+        let best = (&*entities, &predicted_shots).join()
+            .filter_map(|(entity, predicted)| {
+                let age = util::duration_to_secs(predicted.fired_at.elapsed());
+                let distance = (arena.unwrap(shot.body.pos, predicted.origin.pos) - shot.body.pos).norm();
+                let cos = normalized(predicted.origin.vel).dot(&server_dir);
+
+                let matches = age <= constant::shot::MATCH_MAX_AGE &&
+                    distance <= constant::shot::MATCH_MAX_DISTANCE &&
+                    cos >= constant::shot::MATCH_MIN_COS;
+
+                if matches { Some((entity, age, distance)) } else { None }
+            })
+            .min_by(|&(_, _, a), &(_, _, b)| a.partial_cmp(&b).unwrap_or(Ordering::Equal));
+
+        let (entity, age) = match best {
+            Some((entity, age, _)) => (entity, age),
+            None => return false,
+        };
+
+        // where the server shot is by now
+        let mut adopted = shot.body.clone();
+        adopted.pos = arena.wrap(adopted.pos + adopted.vel * age);
+
+        if let Some(body) = bodies.get_mut(entity) {
+            let drawn_at = arena.unwrap(adopted.pos, body.pos);
+            offsets.insert(entity, components::VisualOffset(drawn_at - adopted.pos));
+
+            *body = adopted;
+        }
+
+        ttls.insert(entity, components::TimeToLive::with_remaining(shot.ttl - age, shot.ttl));
+        predicted_shots.remove(entity);
+        // confirmed for sure, keep it away from the cleanup
+        shot_network_ids.remove(entity);
+
+        true
+    }
+
+    // Returns false (and warns) if the server creates an id that already exists.
+    fn check_new_network_id(&self, id: u32) -> bool {
+        let known = self.world.read_resource::<resources::NetworkEntityMap>().contains(id);
+        if known {
+            println!("Ignoring duplicate create for entity {} from server", id);
+        }
+
+        !known
+    }
+
+    fn destroy_entity(&mut self, id: u32) {
+        let entity = self.world.write_resource::<resources::NetworkEntityMap>().remove(id);
+
+        if let Some(entity) = entity {
+            self.confirm_contacts(entity);
+
+            self.world.delete_entity(entity)
+                .expect("Deleting already deleted entity?!");
+        }
+    }
+
+    /// The server damaged or destroyed `entity`, so a predicted contact with it did happen:
+    /// the shot hidden by it is gone for good.
+    fn confirm_contacts(&mut self, entity: Entity) {
+        let shot = self.world.write_resource::<resources::PredictedContacts>().confirm(entity);
+
+        if let Some(shot) = shot {
+            if self.world.is_alive(shot) {
+                self.world.delete_entity(shot)
+                    .expect("Deleting already deleted entity?!");
+            }
+        }
+    }
+
+    fn apply_updates(&mut self, server_timestamp: u64, updates: &HashMap<u32, astero::Update>) {
+        let mut violations = Vec::new();
+        let mut local_update = None;
+        let mut life_updates = Vec::new();
+        let mut unknown_entity_updates = 0;
+
+        // arrival time until the server clock is known
+        let timestamp = {
+            let server_clock = self.world.read_resource::<resources::ServerClock>();
+            server_clock.to_local(server_timestamp)
+                .unwrap_or_else(util::cur_time_in_millis)
+        };
+
+        {
+            let network_entities = self.world.read_resource::<resources::NetworkEntityMap>();
+            let mut interp_buffers = self.world.write::<components::InterpolationBuffer>();
+
+            for (&id, update) in updates {
+                let maybe_entity = network_entities.get_entity(id);
+
+                if maybe_entity.is_none() {
+                    unknown_entity_updates += 1;
+                }
+
+                if let Some(ent) = maybe_entity {
+                    let update = match validation::update(id, update) {
+                        Ok(update) => update,
+                        Err(violation) => {
+                            violations.push(violation);
+                            continue;
+                        }
+                    };
+
+                    match update {
+                        validation::Update::Player(ref player)
+                        if self.player_id == i64::from(player.id) => {
+                            local_update = Some(player.clone());
+                            if let Some(life) = player.life {
+                                life_updates.push((ent, life));
+                            }
+                        }
+
+                        validation::Update::Player(ref player) => {
+                            match interp_buffers.get_mut(ent) {
+                                Some(interp_buf) => interp_buf.add(timestamp, player.body.clone()),
+                                None => violations.push(validation::Violation::KindMismatch(id)),
+                            }
+                            if let Some(life) = player.life {
+                                life_updates.push((ent, life));
+                            }
+                        }
+                        validation::Update::Asteroid(ref asteroid) => {
+                            match interp_buffers.get_mut(ent) {
+                                Some(interp_buf) => interp_buf.add(timestamp, asteroid.body.clone()),
+                                None => violations.push(validation::Violation::KindMismatch(id)),
+                            }
+                            if let Some(life) = asteroid.life {
+                                life_updates.push((ent, life));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(local_update) = local_update {
+            self.reconcile(&local_update);
+        }
+
+        let mut damaged = Vec::new();
+        {
+            let mut lives = self.world.write::<components::Life>();
+            let mut damage_events = self.world.write_resource::<resources::DamageEvents>();
+
+            for (entity, life) in life_updates {
+                let damage = match lives.get_mut(entity) {
+                    Some(cur_life) => cur_life.set(life),
+                    None => continue,
+                };
+
+                if damage > 0.0 {
+                    damage_events.0.push(resources::DamageEvent { entity, amount: damage });
+                    damaged.push(entity);
+                }
+            }
+        }
+
+        for entity in damaged {
+            self.confirm_contacts(entity);
+        }
+
+        self.world.write_resource::<resources::Diagnostics>().unknown_entity_updates += unknown_entity_updates;
+
+        for violation in violations {
+            self.protocol_violation(&violation);
+        }
+    }
+
+    /// Restores the local player to the body the server confirmed and replays
+    /// the inputs the server hasn't handled yet on top of it.
+    fn reconcile(&mut self, update: &validation::PlayerUpdate) {
+        use specs::Join;
+
+        if let Some(current_fire_timeout) = update.current_fire_timeout {
+            let server_clock = self.world.read_resource::<resources::ServerClock>();
+            let corrected_timeout = current_fire_timeout - server_clock.compensation() as f32;
+
+            let mut cannons = self.world.write::<components::Cannon>();
+            for (cannon, ) in (&mut cannons, ).join() {
+                cannon.set_current_timeout(corrected_timeout);
+            }
+        }
+
+        if !update.shot_confirmed {
+            let mut unconfirmed_shots = self.world.write::<components::ShotNetworkId>();
+            (&mut unconfirmed_shots, ).join()
+                .filter(|(shot_id, )| shot_id.0 == update.last_handled_input)
+                .for_each(|(shot_id, )| shot_id.1 = false);
+        }
+
+        // removes the shots the server didn't confirm, nothing else has to run again
+        {
+            use specs::RunNow;
+
+            systems::UnconfirmedShotCleanup.run_now(&self.world.res);
+        }
+        self.world.maintain();
+
+        let correction = {
+            let controllables = self.world.read::<components::Controllable>();
+            let accelerators = self.world.read::<components::Accelerator>();
+            let mut bodies = self.world.write::<components::Body>();
+            let arena = self.world.read_resource::<resources::Arena>();
+
+            let (_, accel, body) = match (&controllables, &accelerators, &mut bodies).join().next() {
+                Some(player) => player,
+                None => return,
+            };
+
+            // what was predicted for the moment the server body corresponds to
+            let mut predicted = None;
+            let mut replayed = update.body.clone();
+
+            for pending in self.pending_inputs.get_state_after(update.last_handled_input) {
+                if predicted.is_none() {
+                    predicted = Some(pending.body.clone());
+                }
+
+                for _ in 0..pending.full_update_steps {
+                    replayed = systems::predict_movement(&replayed, &pending.input, accel, &arena);
+                }
+            }
+
+            let predicted_pos = match predicted {
+                Some(Some(predicted)) => predicted.pos,
+                Some(None) => update.body.pos,
+                // nothing pending, the prediction is the current body
+                None => body.pos,
+            };
+            let error = arena.unwrap(update.body.pos, predicted_pos) - update.body.pos;
+
+            let correction = resources::Correction {
+                from: body.pos,
+                to: replayed.pos,
+                error: error.norm(),
+                at: Instant::now(),
+            };
+
+            *body = replayed;
+
+            correction
+        };
+
+        self.world.write_resource::<resources::Corrections>().record(correction);
+    }
+
+    fn protocol_violation(&mut self, violation: &validation::Violation) {
+        let mut diagnostics = self.world.write_resource::<resources::Diagnostics>();
+        diagnostics.protocol_violation(violation);
+    }
+}
+
+
+fn normalized(vec: Vector2) -> Vector2 {
+    let norm = vec.norm();
+    if norm > 0.0 {
+        vec / norm
+    } else {
+        vec
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ggez::event::Keycode;
+    use ggez::graphics::Point2;
+
+    use specs::Join;
+
+    use components;
+    use config::Config;
+    use constant;
+    use msg::Msg;
+    use proto::astero;
+    use resources;
+    use transport::{
+        LoopbackPeer,
+        LoopbackTransport,
+    };
+
+    use super::Game;
+
+
+    const PLAYER_ID: u32 = 1;
+
+    fn start_body() -> components::Body {
+        components::Body {
+            size: 30.0,
+            ..Default::default()
+        }
+    }
+
+    fn join() -> (Game<'static, 'static>, LoopbackPeer) {
+        let (transport, peer) = LoopbackTransport::pair();
+        let mut game = Game::new(Box::new(transport), &Config::default());
+
+        let mut player = astero::Player::default();
+        player.id = PLAYER_ID;
+        player.body = start_body().into();
+
+        peer.send(Msg::JoinAck(player));
+        game.update(0.0);
+
+        (game, peer)
+    }
+
+    // List with a single update of the local player
+    fn player_update(timestamp: u64, body: components::Body, last_handled_input: u32) -> Msg {
+        let mut entity = astero::update::Entity::Player(Default::default());
+        if let astero::update::Entity::Player(ref mut update) = entity {
+            update.id = PLAYER_ID;
+            update.body = body.into();
+            update.last_handled_input = Some(last_handled_input);
+        }
+
+        let mut update = astero::Update::default();
+        update.entity = Some(entity);
+
+        let mut msg = astero::server::Msg::List(Default::default());
+        if let astero::server::Msg::List(ref mut list) = msg {
+            list.timestamp = timestamp;
+            list.updates.insert(PLAYER_ID, update);
+        }
+
+        Msg::FromServer(msg)
+    }
+
+    fn local_body(game: &Game) -> components::Body {
+        let controllables = game.world.read::<components::Controllable>();
+        let bodies = game.world.read::<components::Body>();
+
+        let (_, body) = (&controllables, &bodies).join().next()
+            .expect("No local player");
+
+        body.clone()
+    }
+
+    #[test]
+    fn reconciliation_replays_unacknowledged_input() {
+        let (mut game, peer) = join();
+
+        let (input, update) = {
+            let mut input = game.world.write_resource::<resources::Input>();
+            let update = input.key_down(Keycode::Up, false);
+
+            (input.clone(), update)
+        };
+        game.update_input(input, update);
+
+        game.update(constant::physics::DELTA_TIME * 10.5);
+        let predicted = local_body(&game);
+        assert!((predicted.pos - start_body().pos).norm() > 0.0, "thrust didn't move the ship");
+
+        // server hasn't seen the input yet: replaying it lands where prediction did
+        peer.send(player_update(1, start_body(), 0));
+        game.update(0.0);
+
+        let replayed = local_body(&game);
+        assert!((replayed.pos - predicted.pos).norm() < 1e-4);
+        assert!((replayed.vel - predicted.vel).norm() < 1e-4);
+        {
+            let corrections = game.world.read_resource::<resources::Corrections>();
+            assert_eq!(corrections.count, 1);
+            assert!(corrections.last.as_ref().map(|correction| correction.error < 1e-4).unwrap_or(false));
+        }
+
+        // input handled, nothing left to replay: the server body is taken as is
+        let mut acked = start_body();
+        acked.pos = Point2::new(5.0, 0.0);
+        peer.send(player_update(2, acked, 1));
+        game.update(0.0);
+
+        let corrected = local_body(&game);
+        assert!((corrected.pos - Point2::new(5.0, 0.0)).norm() < 1e-4);
+    }
+
+    #[test]
+    fn replayed_input_keeps_its_sequence_number() {
+        let (mut game, peer) = join();
+        while peer.try_recv().is_some() {}
+
+        let mut input = astero::Input::default();
+        input.sequence_num = 7;
+        input.accel = Some(-1);
+        peer.send(Msg::ReplayedInput(input));
+        game.update(0.0);
+
+        let mut sent = Vec::new();
+        while let Some(msg) = peer.try_recv() {
+            if let Msg::ToServer(astero::client::Msg::Input(input)) = msg {
+                sent.push(input.sequence_num);
+            }
+        }
+        assert_eq!(sent, vec![7]);
+
+        // the recorded ack of that input leaves nothing to replay
+        let mut acked = start_body();
+        acked.pos = Point2::new(5.0, 0.0);
+        peer.send(player_update(1, acked, 7));
+        game.update(0.0);
+
+        assert!((local_body(&game).pos - Point2::new(5.0, 0.0)).norm() < 1e-4);
+    }
+}
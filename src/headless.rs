@@ -0,0 +1,59 @@
+use std::thread;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use config::Config;
+use constant::physics;
+use game::{
+    Game,
+    Status,
+};
+use transport::Transport;
+use util;
+
+
+/// Runs the client without a window: the same world, dispatcher, networking
+/// and input buffer are driven at the fixed physics step.
+/// Stops when the server is gone or after `duration` (if any).
+pub fn run(transport: Box<Transport>, config: &Config) {
+    let mut game = Game::new(transport, config);
+    let duration = config.duration;
+
+    let step = Duration::from_millis((physics::DELTA_TIME * 1000.0) as u64);
+
+    let started = Instant::now();
+    let mut last_update = started;
+    let mut frames: u64 = 0;
+
+    println!("Running headless client...");
+
+    loop {
+        let now = Instant::now();
+        let frame_time = util::duration_to_secs(now - last_update);
+        last_update = now;
+
+        if let Status::Disconnected = game.update(frame_time) {
+            break;
+        }
+
+        game.interpolate();
+        frames += 1;
+
+        if let Some(duration) = duration {
+            if now - started >= duration {
+                break;
+            }
+        }
+
+        thread::sleep(step);
+    }
+
+    game.stop();
+
+    println!(
+        "Headless client finished after {:.1} s ({} frames)",
+        util::duration_to_secs(started.elapsed()), frames
+    );
+}
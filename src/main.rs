@@ -42,6 +42,13 @@ extern crate bytes;
 extern crate prost;
 #[macro_use] extern crate prost_derive;
 
+use std::io;
+#[cfg(feature = "fake-server")]
+use std::net::{
+    IpAddr,
+    Ipv4Addr,
+    SocketAddr,
+};
 use std::path;
 
 use ggez::{
@@ -52,326 +59,88 @@ use ggez::{
     event::*,
 };
 
-use specs::{
-    World,
-    DispatcherBuilder,
-    Dispatcher,
-};
-
 mod components;
 mod resources;
 mod systems;
+mod transport;
 
+mod collision;
+mod config;
 mod constant;
+mod debug_overlay;
+#[cfg(any(test, feature = "fake-server"))]
+mod fake_server;
+mod game;
+mod headless;
 mod msg;
 mod proto;
 mod util;
+mod validation;
 
-use proto::astero;
-
-// TODO: move to the server
-//    fn collided<M: Movable>(&mut self, other: &M) -> bool {
-//        let self_body = self.get_body();
-//        let other_body = other.get_body();
-//
-//        let distance = ggez::nalgebra::distance(&self_body.pos, &other_body.pos);
-//
-//        distance < (self_body.size / 2.0 + other_body.size / 2.0)
-//    }
+#[cfg(feature = "fake-server")]
+use fake_server::FakeServer;
+use game::Game;
+use transport::Transport;
 
 
 struct MainState<'a, 'b> {
-    world: World,
-    dispatcher: Dispatcher<'a, 'b>,
-
+    game: Game<'a, 'b>,
     assets: resources::Assets,
-    client: resources::Client,
-    pending_inputs: resources::InputBuffer,
-
-    time_acc: f32,
-    last_server_update_timestamp: u64,
-    player_id: i64,
+    reconnecting_text: graphics::Text,
+    // inputs come from the recording, keyboard is ignored
+    replaying: bool,
+    debug_overlay: bool,
+    // camera has found the local player and is following it
+    following: bool,
 }
 
 impl<'a, 'b> MainState<'a, 'b> {
-    fn new(ctx: &mut Context) -> GameResult<Self> {
+    fn new(ctx: &mut Context, transport: Box<Transport>, config: &config::Config) -> GameResult<Self> {
         graphics::set_background_color(ctx, (0, 0, 0, 255).into());
 
         print_instructions();
 
-        let mut world = World::new();
-
-        world.add_resource(resources::Input::new());
-        world.add_resource(resources::ServerClock::new());
-        world.add_resource(resources::CurrentSystemRunMode);
-        world.add_resource(resources::UnconfirmedShotId(None));
-
-        world.register::<components::Sprite>();
-        world.register::<components::Body>();
-        world.register::<components::Nickname>();
-        world.register::<components::Color>();
-        world.register::<components::Life>();
-        world.register::<components::StickyHealthBar>();
-        world.register::<components::StaticHealthBar>();
-        world.register::<components::Controllable>();
-        world.register::<components::NetworkId>();
-        world.register::<components::TimeToLive>();
-        world.register::<components::Accelerator>();
-        world.register::<components::InterpolationBuffer>();
-        world.register::<components::BlenderBody>();
-        world.register::<components::Cannon>();
-        world.register::<components::ShotNetworkId>();
-
-        let dispatcher = DispatcherBuilder::new()
-            .add(systems::KinematicsPrediction, "KinematicsPrediction", &[])
-            .add(systems::Interpolation, "Interpolation", &[])
-            .add(systems::Shooting, "Shooting", &[])
-            .add(systems::UnconfirmedShotCleanup, "UnconfirmedShotCleanup", &[])
-            .build();
-
-        let nickname = util::cur_user_name();
-
-        let client = resources::Client::start();
-        client.send(msg::Msg::JoinGame(nickname));
-        println!("Connecting to server...");
-
-        let s = Self {
-            world,
-            dispatcher,
+        let assets = resources::Assets::new(ctx)?;
+        let reconnecting_text = graphics::Text::new(ctx, "Reconnecting...", &assets.font)?;
 
-            assets: resources::Assets::new(ctx)?,
-            client,
-            pending_inputs: resources::InputBuffer::new(),
+        let mut game = Game::new(transport, config);
+        game.world.add_resource(resources::Camera::new(
+            ctx.conf.window_mode.width as f32,
+            ctx.conf.window_mode.height as f32,
+        ));
 
-            time_acc: 0.0,
-            last_server_update_timestamp: 0,
-            player_id: -1,
+        let s = Self {
+            assets,
+            reconnecting_text,
+            replaying: config.replay.is_some(),
+            debug_overlay: false,
+            following: false,
+            game,
         };
 
         Ok(s)
     }
 
-    fn world_to_screen_coords(&self, ctx: &Context, point: graphics::Point2) -> graphics::Point2 {
-        let width = ctx.conf.window_mode.width as f32;
-        let height = ctx.conf.window_mode.height as f32;
-        let x = point.x + width / 2.0;
-        let y = height - (point.y + height / 2.0);
-
-        graphics::Point2::new(x, y)
-    }
-
-    fn update_input(&mut self, cur_input: resources::Input, maybe_update: Option<proto::astero::Input>) {
+    fn update_camera(&mut self, frame_time: f32) {
         use specs::Join;
 
-        if let Some(mut update) = maybe_update {
-            update.sequence_num = self.pending_inputs.add(cur_input);
-
-            let cannons = self.world.read::<components::Cannon>();
-            let bodies = self.world.read::<components::Body>();
-            let maybe_player = (&cannons, &bodies).join().next();
-
-            if let Some((cannon, body)) = maybe_player {
-                if cannon.ready_to_fire() {
-                    update.body_then = Some(body.clone().into());
-                }
-
-                {
-                    let mut unconfirmed_shot_id = self.world.write_resource::<resources::UnconfirmedShotId>();
-                    unconfirmed_shot_id.0 = Some(update.sequence_num);
-                }
-            }
-
-            self.client.send(msg::Msg::ToServer(update.into()));
-        }
-    }
+        let world = &self.game.world;
 
-    fn handle_message(&mut self, ctx: &mut Context, msg: msg::Msg) -> GameResult<()> {
-        use specs::Join;
+        let controllables = world.read::<components::Controllable>();
+        let bodies = world.read::<components::Body>();
+        let arena = world.read_resource::<resources::Arena>();
+        let mut camera = world.write_resource::<resources::Camera>();
 
-        match msg {
-            msg::Msg::JoinAck(cur_player) => {
-                self.player_id = i64::from(cur_player.id);
-
-                self.world.create_entity()
-                    .with(components::Body::new(&cur_player.body))
-                    .with(components::BlenderBody::new())
-                    .with(components::Accelerator::new(
-                        constant::physics::PLAYER_ACCELERATION,
-                        constant::physics::PLAYER_DECELERATION
-                    ))
-                    .with(components::Cannon::new(constant::FIRE_TIMEOUT))
-                    .with(components::Color(constant::colors::GREEN))
-                    .with(components::Life::new(cur_player.life.expect("Got empty life from server")))
-                    .with(components::StaticHealthBar::new(
-                        10 as f32,
-                        ctx.conf.window_mode.height as f32 - constant::hud::HEALTH_BAR_SIZE - 5.0,
-                        (ctx.conf.window_mode.width / 2) as f32,
-                        constant::hud::HEALTH_BAR_SIZE))
-                    .with(components::StickyHealthBar {})
-                    .with(components::Sprite(resources::SpriteKind::Player))
-                    .with(components::Nickname::new(
-                        ctx,
-                        &cur_player.nickname.expect("Got empty nickname from server"),
-                        &self.assets.small_font)?)
-                    .with(components::Controllable {})
-                    .with(components::NetworkId(cur_player.id))
-                    .build();
-            },
-
-            msg::Msg::FromServer(msg) => {
-                match msg {
-                    astero::server::Msg::Create(create) => {
-                        let entity = create.entity.expect("Got empty create entity from server");
-
-                        match entity {
-                            astero::create::Entity::Player(other) => {
-                                self.world.create_entity()
-                                    .with(components::Body::new(&other.body))
-                                    .with(components::Color(constant::colors::RED))
-                                    .with(components::Life::new(other.life.expect("Got empty life from server")))
-                                    .with(components::StickyHealthBar {})
-                                    .with(components::Sprite(resources::SpriteKind::Player))
-                                    .with(components::Nickname::new (
-                                        ctx,
-                                        &other.nickname.expect("Got empty nickname from server"),
-                                        &self.assets.small_font
-                                    )?)
-                                    .with(components::NetworkId(other.id))
-                                    .with(components::InterpolationBuffer::new())
-                                    .build();
-                            }
-                            astero::create::Entity::Asteroid(ref asteroid) => {
-                                self.world.create_entity()
-                                    .with(components::Body::new(&asteroid.body))
-                                    .with(components::Color(constant::colors::RED))
-                                    .with(components::Life::new(asteroid.life.expect("Got empty life from server")))
-                                    .with(components::StickyHealthBar {})
-                                    .with(components::Sprite(resources::SpriteKind::Asteroid))
-                                    .with(components::NetworkId(asteroid.id))
-                                    .with(components::InterpolationBuffer::new())
-                                    .build();
-                            }
-                            astero::create::Entity::Shot(ref shot) => {
-                                self.world.create_entity()
-                                    .with(components::Body::new(&shot.body))
-                                    .with(components::Sprite(resources::SpriteKind::Shot))
-                                    .with(components::TimeToLive::new(shot.ttl))
-                                    .build();
-                            }
-                        }
-                    }
-                    astero::server::Msg::Destroy(entity_to_destroy) => {
-                        let entity = {
-                            let entities = self.world.entities();
-                            let network_ids = self.world.read::<components::NetworkId>();
-
-                            (&*entities, &network_ids).join()
-                                .find(|&(_entity, network_id)| entity_to_destroy.id == network_id.0)
-                                .map(|(entity, _network_id)| entity)
-                        };
-
-                        if let Some(entity) = entity {
-                            self.world.delete_entity(entity)
-                                .expect("Deleting already deleted entity?!");
-                        }
-                    },
-                    astero::server::Msg::List(updates) => {
-                        if updates.timestamp < self.last_server_update_timestamp {
-                            return Ok(());
-                        }
-                        self.last_server_update_timestamp = updates.timestamp;
-
-                        let entities = self.world.entities();
-                        let network_ids = self.world.read::<components::NetworkId>();
-                        let mut bodies = self.world.write::<components::Body>();
-                        let mut interp_buffers = self.world.write::<components::InterpolationBuffer>();
-
-                        for (ent, network_id, ) in (&*entities, &network_ids, ).join() {
-                            let maybe_update = updates.updates.get(&network_id.0);
-
-                            if let Some(update) = maybe_update {
-                                let entity = update.entity.as_ref().expect("Got empty entity update from server");
-
-                                match entity {
-                                    astero::update::Entity::Player(player)
-                                    if self.player_id == i64::from(player.id) => {
-                                        let maybe_body = bodies.get_mut(ent);
-                                        if let Some(body) = maybe_body {
-                                            *body = components::Body::new(&player.body);
-
-                                            {
-                                                let mut cur_sys_run_mode = self.world.write_resource::<resources::CurrentSystemRunMode>();
-                                                cur_sys_run_mode.0 = resources::SystemRunMode::Reconciliation;
-                                            }
-
-                                            if let Some(current_fire_timeout) = player.current_fire_timeout {
-                                                let server_clock = self.world.read_resource::<resources::ServerClock>();
-                                                let corrected_timeout = current_fire_timeout - server_clock.compensation() as f32;
-
-                                                let mut cannons = self.world.write::<components::Cannon>();
-                                                for (cannon, ) in (&mut cannons, ).join() {
-                                                    cannon.set_current_timeout(corrected_timeout);
-                                                }
-                                            }
-
-                                            let last_handled_input = player.last_handled_input
-                                                .expect("Got empty last handled input from server");
-
-                                            if !player.shot_confirmed.unwrap_or(true) {
-                                                let mut unconfirmed_shots = self.world.write::<components::ShotNetworkId>();
-                                                (&mut unconfirmed_shots, ).join()
-                                                    .filter(|(shot_id, )| shot_id.0 == last_handled_input)
-                                                    .for_each(|(shot_id, )| shot_id.1 = false);
-                                            }
-
-                                            for pending in self.pending_inputs.get_state_after(last_handled_input) {
-                                                {
-                                                    let mut input = self.world.write_resource::<resources::Input>();
-                                                    *input = pending.input.clone();
-                                                }
-
-                                                for _ in 0..pending.full_update_steps {
-                                                    self.dispatcher.dispatch(&self.world.res);
-                                                }
-                                            }
-                                        }
-                                    }
-
-                                    astero::update::Entity::Player(player) => {
-                                        let interp_buf = interp_buffers.get_mut(ent)
-                                            .expect("No interpolation buffer attached to remote player?!");
-                                        interp_buf.add(&player.body);
-                                    }
-                                    astero::update::Entity::Asteroid(asteroid) => {
-                                        let interp_buf = interp_buffers.get_mut(ent)
-                                            .expect("No interpolation buffer attached to remote asteroid?!");
-                                        interp_buf.add(&asteroid.body);
-                                    }
-                                }
-                            }
-                        }
-                    },
-                }
+        match (&bodies, &controllables).join().next() {
+            Some((body, _)) if self.following => camera.follow(body.pos, frame_time, &arena),
+            // jump to the player as soon as it appears instead of sliding across the arena
+            Some((body, _)) => {
+                camera.look_at(body.pos, &arena);
+                self.following = true;
             }
-
-            msg::Msg::ServerNotResponding => {
-                println!("Server is not available! Closing game...");
-                ctx.quit()?;
-            }
-
-            msg::Msg::Latency(ref measure) => {
-                let mut server_clock = self.world.write_resource::<resources::ServerClock>();
-                server_clock.update(
-                    measure.timestamp,
-                    measure.server_timestamp.expect("Got empty server timestamp")
-                );
-            },
-
-            msg::Msg::Unknown | msg::Msg::JoinGame(..) | msg::Msg::LeaveGame |
-            msg::Msg::Heartbeat | msg::Msg::ToServer(..) => unreachable!(),
+            // gone until the next JoinAck
+            None => self.following = false,
         }
-
-        Ok(())
     }
 }
 
@@ -381,31 +150,22 @@ fn print_instructions() {
     println!();
     println!("How to play:");
     println!("L/R arrow keys rotate ship, up thrusts, down slows down, space bar fires");
+    println!("+/- zoom in and out");
+    println!("F1 toggles the network conditioner, F2 the debug overlay");
     println!();
 }
 
 impl<'a, 'b> EventHandler for MainState<'a, 'b> {
 
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        while let Ok(msg) = self.client.try_recv() {
-            self.handle_message(ctx, msg)?;
-        }
-
         let frame_time = timer::get_delta(ctx);
         let frame_time = timer::duration_to_f64(frame_time) as f32;
 
-        {
-            let mut cur_system_run_mode = self.world.write_resource::<resources::CurrentSystemRunMode>();
-            cur_system_run_mode.0 = resources::SystemRunMode::Prediction;
+        if let game::Status::Disconnected = self.game.update(frame_time) {
+            ctx.quit()?;
         }
 
-        self.time_acc += frame_time;
-
-        while self.time_acc > constant::physics::DELTA_TIME {
-            self.dispatcher.dispatch(&self.world.res);
-            self.pending_inputs.increase_update_step();
-            self.time_acc -= constant::physics::DELTA_TIME;
-        }
+        self.update_camera(frame_time);
 
         Ok(())
     }
@@ -415,62 +175,151 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
 
         graphics::clear(ctx);
 
+        self.game.interpolate();
+
+        let world = &self.game.world;
+
+        let entities = world.entities();
+        let bodies = world.read::<components::Body>();
+        let blend_bodies = world.read::<components::BlenderBody>();
+        let sprites = world.read::<components::Sprite>();
+
+        let arena = world.read_resource::<resources::Arena>();
+        let camera = world.read_resource::<resources::Camera>();
+        let zoom = camera.zoom();
+
         {
-            let mut cur_sys_run_mode = self.world.write_resource::<resources::CurrentSystemRunMode>();
-            cur_sys_run_mode.0 = resources::SystemRunMode::Interpolation(
-                util::cur_time_in_millis() - 1000 / 30,
-                self.time_acc / constant::physics::DELTA_TIME
+            let top_left = camera.world_to_screen(
+                graphics::Point2::new(-arena.width / 2.0, arena.height / 2.0)
             );
-        }
 
-        self.dispatcher.dispatch(&self.world.res);
+            let old_color = graphics::get_color(ctx);
+            graphics::set_color(ctx, constant::colors::LIGHT_BLUE)?;
+            graphics::rectangle(
+                ctx, graphics::DrawMode::Line(constant::hud::ARENA_BORDER_WIDTH),
+                graphics::Rect::new(top_left.x, top_left.y, arena.width * zoom, arena.height * zoom)
+            )?;
+            graphics::set_color(ctx, old_color)?;
+        }
 
-        let entities = self.world.entities();
-        let bodies = self.world.read::<components::Body>();
-        let blend_bodies = self.world.read::<components::BlenderBody>();
-        let sprites = self.world.read::<components::Sprite>();
-
-        for (ent, body, sprite) in (&*entities, &bodies, &sprites).join() {
-            let sprite = self.assets.get_sprite(&sprite.0);
+        let visual_offsets = world.read::<components::VisualOffset>();
 
+        // every on-screen spot of an entity: blended if predicted, shifted by what's left
+        // of a correction, all images in a wrapping arena
+        let screen_positions = |ent, body: &components::Body| -> Vec<graphics::Point2> {
             let pos = blend_bodies.get(ent)
                 .and_then(|bb| bb.get_blended())
                 .and_then(|b| Some(b.pos))
                 .unwrap_or(body.pos);
-            let pos = self.world_to_screen_coords(ctx,pos);
-
-            graphics::draw_ex(ctx, sprite, graphics::DrawParam {
-                dest: pos,
-                rotation: body.rot,
-                offset: graphics::Point2::new(0.5, 0.5),
-                scale: graphics::Point2::new(
-                    body.size / sprite.width() as f32,
-                    body.size / sprite.height() as f32
-                ),
-                ..Default::default()
-            })?;
+            let pos = match visual_offsets.get(ent) {
+                Some(offset) => pos + offset.0,
+                None => pos,
+            };
+
+            arena.images(pos).into_iter()
+                .filter(|&pos| camera.is_visible(pos, body.size))
+                .map(|pos| camera.world_to_screen(pos))
+                .collect()
+        };
+
+        let ttls = world.read::<components::TimeToLive>();
+        let hit_flashes = world.read::<components::HitFlash>();
+
+        for (ent, body, sprite) in (&*entities, &bodies, &sprites).join() {
+            let sprite = self.assets.get_sprite(&sprite.0);
+
+            // short-lived entities fade out at the end of their life
+            let alpha = ttls.get(ent)
+                .map(|ttl| (ttl.fraction() / constant::hud::FADE_OUT_FRACTION).min(1.0))
+                .unwrap_or(1.0);
+
+            // tinted red right after a hit
+            let flash = hit_flashes.get(ent)
+                .map(|hit_flash| hit_flash.intensity())
+                .unwrap_or(0.0);
+            let tint = 1.0 - flash * constant::hud::HIT_FLASH_STRENGTH;
+
+            for pos in screen_positions(ent, body) {
+                graphics::draw_ex(ctx, sprite, graphics::DrawParam {
+                    dest: pos,
+                    rotation: body.rot,
+                    color: Some(graphics::Color::new(1.0, tint, tint, alpha)),
+                    offset: graphics::Point2::new(0.5, 0.5),
+                    scale: graphics::Point2::new(
+                        body.size * zoom / sprite.width() as f32,
+                        body.size * zoom / sprite.height() as f32
+                    ),
+                    ..Default::default()
+                })?;
+            }
+        }
+
+        let nicknames = world.read::<components::Nickname>();
+        let colors = world.read::<components::Color>();
+
+        for (ent, body, nickname, color) in (&*entities, &bodies, &nicknames, &colors).join() {
+            for pos in screen_positions(ent, body) {
+                nickname.draw(ctx, &mut self.assets, pos, body.size * zoom, color.0)?;
+            }
         }
 
-        let nicknames = self.world.read::<components::Nickname>();
-        let colors = self.world.read::<components::Color>();
+        let lives = world.read::<components::Life>();
+        let sticky_health_bars = world.read::<components::StickyHealthBar>();
 
-        for (body, nickname, color) in (&bodies, &nicknames, &colors).join() {
-            let pos = self.world_to_screen_coords(ctx, body.pos);
-            nickname.draw(ctx, pos, body.size, color.0)?;
+        for (ent, body, life, color, sticky_health_bar) in (&*entities, &bodies, &lives, &colors, &sticky_health_bars).join() {
+            for pos in screen_positions(ent, body) {
+                sticky_health_bar.draw(ctx, pos, body.size * zoom, life.fraction(), life.trail_fraction(), color.0)?;
+            }
         }
 
-        let lives = self.world.read::<components::Life>();
-        let sticky_health_bars = self.world.read::<components::StickyHealthBar>();
+        let damage_numbers = world.read::<components::DamageNumber>();
+
+        for (ent, body, damage_number) in (&*entities, &bodies, &damage_numbers).join() {
+            let alpha = ttls.get(ent)
+                .map(|ttl| ttl.fraction())
+                .unwrap_or(1.0);
+            let mut color = constant::colors::RED;
+            color.a = alpha;
 
-        for (body, life, color, sticky_health_bar) in (&bodies, &lives, &colors, &sticky_health_bars).join() {
-            let pos = self.world_to_screen_coords(ctx, body.pos);
-            sticky_health_bar.draw(ctx, pos, body.size, life.fraction(), color.0)?;
+            for pos in screen_positions(ent, body) {
+                damage_number.draw(ctx, &mut self.assets, pos, color)?;
+            }
         }
 
-        let static_health_bars = self.world.read::<components::StaticHealthBar>();
+        let static_health_bars = world.read::<components::StaticHealthBar>();
 
         for (life, static_health_bar) in (&lives, &static_health_bars).join() {
-            static_health_bar.draw(ctx, life.fraction())?;
+            static_health_bar.draw(ctx, life.fraction(), life.trail_fraction())?;
+        }
+
+        let minimaps = world.read::<components::Minimap>();
+        let network_ids = world.read::<components::NetworkId>();
+        let controllables = world.read::<components::Controllable>();
+
+        for (minimap, ) in (&minimaps, ).join() {
+            let dots = (&bodies, &colors, &network_ids).join()
+                .map(|(body, color, _)| (body.pos, body.size, color.0));
+            minimap.draw(ctx, &arena, dots)?;
+
+            // remote players are the ones with nicknames
+            for (body, color, _, ()) in (&bodies, &colors, &nicknames, !&controllables).join() {
+                let pos = arena.unwrap(camera.center(), body.pos);
+                if !camera.is_visible(pos, body.size) {
+                    minimap.draw_edge_arrow(ctx, camera.world_to_screen(pos), color.0)?;
+                }
+            }
+        }
+
+        if self.debug_overlay {
+            debug_overlay::draw(ctx, world, &self.assets.small_font)?;
+        }
+
+        if self.game.is_reconnecting() {
+            let dest = graphics::Point2::new(
+                (ctx.conf.window_mode.width / 2) as f32 - (self.reconnecting_text.width() / 2) as f32,
+                (ctx.conf.window_mode.height / 2) as f32 - (self.reconnecting_text.height() / 2) as f32,
+            );
+            graphics::draw(ctx, &self.reconnecting_text, dest, 0.0)?;
         }
 
         graphics::present(ctx);
@@ -484,38 +333,157 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
             return;
         }
 
+        if let Keycode::F1 = keycode {
+            if !repeat {
+                self.game.toggle_network_conditioner();
+            }
+            return;
+        }
+
+        if let Keycode::F2 = keycode {
+            if !repeat {
+                self.debug_overlay = !self.debug_overlay;
+            }
+            return;
+        }
+
+        match keycode {
+            Keycode::Equals | Keycode::KpPlus => {
+                self.game.world.write_resource::<resources::Camera>().zoom_by(constant::camera::ZOOM_STEP);
+                return;
+            }
+            Keycode::Minus | Keycode::KpMinus => {
+                self.game.world.write_resource::<resources::Camera>().zoom_by(1.0 / constant::camera::ZOOM_STEP);
+                return;
+            }
+            _ => {}
+        }
+
+        if self.replaying {
+            return;
+        }
+
         let (input, maybe_update) = {
-            let mut input = self.world.write_resource::<resources::Input>();
+            let mut input = self.game.world.write_resource::<resources::Input>();
             let maybe_update = input.key_down(keycode, repeat);
 
             (input.clone(), maybe_update)
         };
 
-        self.update_input(input, maybe_update);
+        self.game.update_input(input, maybe_update);
     }
 
     fn key_up_event(&mut self, _ctx: &mut Context, keycode: Keycode, _keymod: Mod, _repeat: bool) {
+        if self.replaying {
+            return;
+        }
+
         let (input, maybe_update) = {
-            let mut input = self.world.write_resource::<resources::Input>();
+            let mut input = self.game.world.write_resource::<resources::Input>();
             let maybe_update = input.key_up(keycode);
 
             (input.clone(), maybe_update)
         };
 
-        self.update_input(input, maybe_update);
+        self.game.update_input(input, maybe_update);
     }
 
     fn quit_event(&mut self, _ctx: &mut Context) -> bool {
-        self.client.stop();
+        self.game.stop();
 
         false
     }
 }
 
+#[cfg(feature = "fake-server")]
+fn start_fake_server(config: &config::Config) -> io::Result<Option<FakeServer>> {
+    let fake_server_config = match config.fake_server {
+        Some(ref fake_server_config) => fake_server_config.clone(),
+        None => return Ok(None),
+    };
+
+    let bind = SocketAddr::new(IpAddr::V4(Ipv4Addr::localhost()), 0);
+    let server = FakeServer::start(bind, fake_server_config)?;
+    println!("Started fake server on {}", server.addr());
+
+    Ok(Some(server))
+}
+
+// built without the fake server, `Config` refuses to enable it, so there never is one
+#[cfg(not(feature = "fake-server"))]
+enum FakeServer {}
+
+#[cfg(not(feature = "fake-server"))]
+impl FakeServer {
+    fn addr(&self) -> std::net::SocketAddr {
+        match *self {}
+    }
+}
+
+#[cfg(not(feature = "fake-server"))]
+fn start_fake_server(_config: &config::Config) -> io::Result<Option<FakeServer>> {
+    Ok(None)
+}
+
+fn open_transport(config: &config::Config) -> io::Result<(Box<Transport>, Option<FakeServer>)> {
+    if let Some(ref replay) = config.replay {
+        println!("Replaying session from {}...", replay);
+        let transport: Box<Transport> = Box::new(transport::FileTransport::open(replay)?);
+
+        return Ok((transport, None));
+    }
+
+    let mut fake_server = None;
+
+    let server = match start_fake_server(config)? {
+        Some(server) => {
+            let addr = server.addr();
+            fake_server = Some(server);
+
+            addr
+        }
+        None => config.server.resolve()?,
+    };
+
+    let mut transport: Box<Transport> = Box::new(transport::UdpTransport::start(server)?);
+    println!("Connecting to server {}...", server);
+
+    if let Some(ref record) = config.record {
+        println!("Recording session to {}...", record);
+        transport = Box::new(transport::RecordingTransport::create(transport, record)?);
+    }
+
+    Ok((transport, fake_server))
+}
+
 fn main() {
+    let config = match config::Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("Invalid configuration: {}", e);
+            return;
+        }
+    };
+
+    // fake server (if any) has to outlive the game
+    let (transport, _fake_server) = match open_transport(&config) {
+        Ok(opened) => opened,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    if config.headless {
+        headless::run(transport, &config);
+        return;
+    }
+
     let mut cb = ContextBuilder::new("Astero", "onkery")
         .window_setup(conf::WindowSetup::default().title("Astero"))
-        .window_mode(conf::WindowMode::default().dimensions(800, 600).vsync(true));
+        .window_mode(conf::WindowMode::default()
+            .dimensions(constant::window::WIDTH, constant::window::HEIGHT)
+            .vsync(true));
 
     let mut path = path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("assets");
@@ -523,7 +491,7 @@ fn main() {
 
     let ctx = &mut cb.build().expect("Failed to build game context");
 
-    match MainState::new(ctx) {
+    match MainState::new(ctx, transport, &config) {
         Err(e) => {
             println!("Could not load the game!");
             println!("Error: {}", e);
@@ -1,4 +1,4 @@
-use std::io;
+use std::fmt;
 
 use proto::{
     astero,
@@ -6,7 +6,35 @@ use proto::{
 };
 
 
-use prost::Message;
+use prost::{
+    self,
+    Message,
+};
+
+
+/// Reasons a datagram from the server could not be turned into a `Msg`.
+#[derive(Debug)]
+pub enum DecodeError {
+    Envelope(prost::DecodeError),
+    EmptyEnvelope,
+    JoinAckPayload(prost::DecodeError),
+    EmptyJoinAck,
+    ProxiedPayload(prost::DecodeError),
+    EmptyProxied,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::Envelope(ref err) => write!(f, "invalid mmob envelope: {}", err),
+            DecodeError::EmptyEnvelope => write!(f, "mmob envelope without message"),
+            DecodeError::JoinAckPayload(ref err) => write!(f, "invalid player in JoinAck: {}", err),
+            DecodeError::EmptyJoinAck => write!(f, "JoinAck without payload"),
+            DecodeError::ProxiedPayload(ref err) => write!(f, "invalid proxied message: {}", err),
+            DecodeError::EmptyProxied => write!(f, "proxied message without payload"),
+        }
+    }
+}
 
 
 #[derive(Debug)]
@@ -14,6 +42,10 @@ pub enum Msg {
     // helper messages (for internal game client usage)
     Unknown,
     ServerNotResponding,
+    ReconnectFailed,
+    Malformed(DecodeError),
+    // input read from a recording, applied as if the player pressed keys
+    ReplayedInput(astero::Input),
 
     JoinGame(String),
     JoinAck(astero::Player),
@@ -26,40 +58,68 @@ pub enum Msg {
 }
 
 impl Msg {
-    pub fn from_bytes(buf: &[u8]) -> io::Result<Self> {
-        let msg = mmob::Server::decode(buf);
+    /// Never panics: anything that is not a well-formed server message is reported as `DecodeError`.
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, DecodeError> {
+        let msg = mmob::Server::decode(buf)
+            .map_err(DecodeError::Envelope)?
+            .msg
+            .ok_or(DecodeError::EmptyEnvelope)?;
 
         let msg = match msg {
-            Err(..) => Msg::Unknown,
-            Ok(msg) => {
-                let msg = msg.msg.expect("Got empty mmob message from server");
-                match msg {
-                    mmob::server::Msg::Heartbeat(..) => Msg::Heartbeat,
-                    mmob::server::Msg::LatencyMeasure(measure) => Msg::Latency(measure),
-                    mmob::server::Msg::JoinAck(ack) => {
-                        if let Some(payload) = ack.payload {
-                            let player = astero::Player::decode(payload)
-                                .expect("Failed to decode player data");
-
-                            Msg::JoinAck(player)
-                        } else {
-                            Msg::Unknown
-                        }
-                    }
-                    mmob::server::Msg::Proxied(msg) => {
-                        let msg = astero::Server::decode(msg.msg)
-                            .expect("Failed to decode proxied message");
-
-                        if let Some(msg) = msg.msg {
-                            Msg::FromServer(msg)
-                        } else {
-                            Msg::Unknown
-                        }
-                    }
-                }
-            },
+            mmob::server::Msg::Heartbeat(..) => Msg::Heartbeat,
+            mmob::server::Msg::LatencyMeasure(measure) => Msg::Latency(measure),
+            mmob::server::Msg::JoinAck(ack) => {
+                let payload = ack.payload.ok_or(DecodeError::EmptyJoinAck)?;
+                let player = astero::Player::decode(&payload[..])
+                    .map_err(DecodeError::JoinAckPayload)?;
+
+                Msg::JoinAck(player)
+            }
+            mmob::server::Msg::Proxied(msg) => {
+                let msg = astero::Server::decode(msg.msg)
+                    .map_err(DecodeError::ProxiedPayload)?
+                    .msg
+                    .ok_or(DecodeError::EmptyProxied)?;
+
+                Msg::FromServer(msg)
+            }
         };
 
         Ok(msg)
     }
-}
\ No newline at end of file
+
+    /// Datagram for messages the client sends to the server, `None` for the rest.
+    pub fn to_client_bytes(&self) -> Option<Vec<u8>> {
+        let msg = match *self {
+            Msg::JoinGame(ref nickname) => {
+                let mut payload = Vec::new();
+                astero::JoinPayload { nickname: nickname.clone() }.encode(&mut payload).ok()?;
+
+                mmob::client::Msg::Join(mmob::JoinGame {
+                    payload: Some(payload),
+                })
+            }
+            Msg::LeaveGame => mmob::client::Msg::Leave(mmob::LeaveGame {}),
+            Msg::Heartbeat => mmob::client::Msg::Heartbeat(mmob::Heartbeat {}),
+            Msg::Latency(ref measure) => mmob::client::Msg::LatencyMeasure(measure.clone()),
+            Msg::ToServer(ref msg) => {
+                let mut client = astero::Client::default();
+                client.msg = Some(msg.clone());
+
+                let mut payload = Vec::new();
+                client.encode(&mut payload).ok()?;
+
+                mmob::client::Msg::Proxied(mmob::Proxied {
+                    msg: payload,
+                })
+            }
+
+            _ => return None,
+        };
+
+        let mut buf = Vec::new();
+        mmob::Client { msg: Some(msg) }.encode(&mut buf).ok()?;
+
+        Some(buf)
+    }
+}
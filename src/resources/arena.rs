@@ -0,0 +1,99 @@
+use ggez::graphics::{
+    Point2,
+    Vector2,
+};
+
+use components::Body;
+
+
+/// What happens to bodies reaching the arena edge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Topology {
+    // velocity is reflected
+    Bounce,
+    // leaving on one side enters on the opposite one
+    Wrap,
+}
+
+
+/// Playing field centered at the origin.
+/// The size comes from the config and has to match the server.
+#[derive(Debug, Clone)]
+pub struct Arena {
+    pub width: f32,
+    pub height: f32,
+    pub topology: Topology,
+}
+
+impl Arena {
+    pub fn new(width: f32, height: f32, topology: Topology) -> Self {
+        Self {
+            width,
+            height,
+            topology,
+        }
+    }
+
+    /// Keeps `body` inside after it moved.
+    pub fn confine(&self, body: &mut Body) {
+        match self.topology {
+            Topology::Bounce => body.bounce_off_edges(self.width / 2.0, self.height / 2.0),
+            Topology::Wrap => body.pos = self.wrap(body.pos),
+        }
+    }
+
+    pub fn wrap(&self, pos: Point2) -> Point2 {
+        if let Topology::Bounce = self.topology {
+            return pos;
+        }
+
+        Point2::new(
+            wrap_coord(pos.x, self.width),
+            wrap_coord(pos.y, self.height),
+        )
+    }
+
+    /// `to` moved by whole arena sizes so that it is as close to `from` as possible.
+    /// Interpolating from `from` to the result takes the way across the edge
+    /// instead of sweeping through the whole arena.
+    pub fn unwrap(&self, from: Point2, to: Point2) -> Point2 {
+        if let Topology::Bounce = self.topology {
+            return to;
+        }
+
+        Point2::new(
+            from.x + wrap_coord(to.x - from.x, self.width),
+            from.y + wrap_coord(to.y - from.y, self.height),
+        )
+    }
+
+    /// Everywhere a body at `pos` can be seen: in a wrapping arena
+    /// it also shows up one arena size away in every direction.
+    pub fn images(&self, pos: Point2) -> Vec<Point2> {
+        if let Topology::Bounce = self.topology {
+            return vec![pos];
+        }
+
+        let mut images = Vec::with_capacity(9);
+        for &dx in &[0.0, -self.width, self.width] {
+            for &dy in &[0.0, -self.height, self.height] {
+                images.push(pos + Vector2::new(dx, dy));
+            }
+        }
+
+        images
+    }
+}
+
+
+// into -size/2..size/2
+fn wrap_coord(value: f32, size: f32) -> f32 {
+    let half = size / 2.0;
+    let wrapped = (value + half) % size;
+
+    if wrapped < 0.0 {
+        wrapped + half
+    } else {
+        wrapped - half
+    }
+}
@@ -1,4 +1,7 @@
-use std::collections::BTreeMap;
+use std::collections::{
+    BTreeMap,
+    HashMap,
+};
 
 use ggez::{
     self,
@@ -18,6 +21,8 @@ pub struct Assets {
     sprites: BTreeMap<SpriteKind, graphics::Image>,
     pub font: graphics::Font,
     pub small_font: graphics::Font,
+    // rendered labels in `small_font`, keyed by their text
+    texts: HashMap<String, graphics::Text>,
 }
 
 impl Assets {
@@ -35,10 +40,20 @@ impl Assets {
             sprites,
             font,
             small_font,
+            texts: HashMap::new(),
         })
     }
 
     pub fn get_sprite(&self, kind: &SpriteKind) -> &graphics::Image {
         self.sprites.get(kind).expect("Sprite not found")
     }
+
+    pub fn get_text(&mut self, ctx: &mut ggez::Context, text: &str) -> ggez::GameResult<&graphics::Text> {
+        if !self.texts.contains_key(text) {
+            let display = graphics::Text::new(ctx, text, &self.small_font)?;
+            self.texts.insert(text.to_string(), display);
+        }
+
+        Ok(&self.texts[text])
+    }
 }
\ No newline at end of file
@@ -0,0 +1,103 @@
+use ggez::graphics::{
+    Point2,
+    Vector2,
+};
+
+use constant::camera;
+use resources::{
+    Arena,
+    Topology,
+};
+
+
+/// Part of the arena shown in the window. Follows the local player,
+/// never shows what's outside the arena (unless the arena is smaller than the view).
+pub struct Camera {
+    // world point shown in the middle of the window
+    center: Point2,
+    zoom: f32,
+    // window size in pixels
+    viewport_width: f32,
+    viewport_height: f32,
+}
+
+impl Camera {
+    pub fn new(viewport_width: f32, viewport_height: f32) -> Self {
+        Self {
+            center: Point2::origin(),
+            zoom: 1.0,
+            viewport_width,
+            viewport_height,
+        }
+    }
+
+    pub fn center(&self) -> Point2 {
+        self.center
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    pub fn zoom_by(&mut self, factor: f32) {
+        self.zoom = (self.zoom * factor).max(camera::MIN_ZOOM).min(camera::MAX_ZOOM);
+    }
+
+    /// Moves towards `target` a bit, the longer `dt` (in seconds) the closer it gets.
+    pub fn follow(&mut self, target: Point2, dt: f32, arena: &Arena) {
+        let target = arena.unwrap(self.center, target);
+        let t = 1.0 - (-camera::STIFFNESS * dt).exp();
+
+        let center = self.center + (target - self.center) * t;
+        self.center = self.clamp(arena.wrap(center), arena);
+    }
+
+    /// Jumps to `target` right away, e.g. when the player appears.
+    pub fn look_at(&mut self, target: Point2, arena: &Arena) {
+        self.center = self.clamp(arena.wrap(target), arena);
+    }
+
+    pub fn world_to_screen(&self, pos: Point2) -> Point2 {
+        let offset = (pos - self.center) * self.zoom;
+
+        // y goes up in the world and down on the screen
+        Point2::new(
+            self.viewport_width / 2.0 + offset.x,
+            self.viewport_height / 2.0 - offset.y,
+        )
+    }
+
+    /// Whether a body of `size` at world `pos` is at least partially in the window.
+    pub fn is_visible(&self, pos: Point2, size: f32) -> bool {
+        let reach = Vector2::new(
+            self.viewport_width / 2.0 / self.zoom + size / 2.0,
+            self.viewport_height / 2.0 / self.zoom + size / 2.0,
+        );
+
+        (pos.x - self.center.x).abs() <= reach.x && (pos.y - self.center.y).abs() <= reach.y
+    }
+
+    fn clamp(&self, center: Point2, arena: &Arena) -> Point2 {
+        // the other side of a wrapping arena is there to be seen
+        if let Topology::Wrap = arena.topology {
+            return center;
+        }
+
+        Point2::new(
+            clamp_axis(center.x, self.viewport_width / self.zoom, arena.width),
+            clamp_axis(center.y, self.viewport_height / self.zoom, arena.height),
+        )
+    }
+}
+
+
+fn clamp_axis(center: f32, view: f32, arena: f32) -> f32 {
+    let max = (arena - view) / 2.0;
+
+    if max <= 0.0 {
+        // whole arena fits, keep it in the middle
+        0.0
+    } else {
+        center.max(-max).min(max)
+    }
+}
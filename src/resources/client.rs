@@ -1,221 +1,279 @@
-use std;
-use std::io;
-use std::net::{SocketAddr, SocketAddrV6, Ipv6Addr, IpAddr};
-use std::time::Duration;
-use std::thread;
-
-use futures;
-use tokio_core::{
-    net::UdpSocket,
-    reactor::Core,
-    reactor::Interval,
+use std::time::{
+    Duration,
+    Instant,
 };
 
+use constant::network;
 use msg::Msg;
-use proto::{
-    astero,
-    mmob,
-};
-use util::cur_time_in_millis;
+use resources::NetworkConditioner;
+use transport::Transport;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionState {
+    // JoinGame sent, waiting for JoinAck
+    Connecting,
+    Connected,
+    // server stopped responding, JoinGame is re-sent with exponential backoff
+    Reconnecting {
+        attempt: u32,
+        next_attempt: Instant,
+    },
+    // gave up reconnecting
+    Failed,
+}
 
 
-use futures::{
-    Stream,
-    Sink,
-    Future,
-};
-use prost::Message;
-use tokio_core::net::UdpCodec;
+/// Connection management on top of a `Transport`:
+/// answers heartbeats, counts timeouts and reconnects.
+/// Traffic in both directions goes through the network conditioner.
+pub struct Client {
+    transport: Box<Transport>,
+    conditioner: NetworkConditioner,
+    timeouts: u32,
+    nickname: String,
+    state: ConnectionState,
+}
 
+impl Client {
+    pub fn new(transport: Box<Transport>, conditioner: NetworkConditioner) -> Self {
+        Self {
+            transport,
+            conditioner,
+            timeouts: 0,
+            nickname: String::new(),
+            state: ConnectionState::Connecting,
+        }
+    }
 
-struct ClientCodec {
-    server: SocketAddr,
-    buf: Vec<u8>,
-}
+    pub fn stop(&mut self) {
+        // don't hold the goodbye back, nobody will flush it
+        self.conditioner.clear();
+        self.transport.send(Msg::LeaveGame);
+        self.transport.stop();
+    }
 
-impl ClientCodec {
-    pub fn new() -> Self {
-        let server = SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::localhost(), 11_111, 0, 0));
+    pub fn send(&mut self, msg: Msg) {
+        if self.conditioner.is_idle() {
+            self.transport.send(msg);
+        } else {
+            self.conditioner.push_outgoing(msg);
+        }
+    }
 
-        Self {
-            server,
-            buf: Vec::new(),
+    pub fn toggle_network_conditioner(&mut self) {
+        self.conditioner.toggle();
+    }
+
+    pub fn join(&mut self, nickname: String) {
+        self.nickname = nickname.clone();
+        self.state = ConnectionState::Connecting;
+        self.send(Msg::JoinGame(nickname));
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Besides messages from the server yields two helper messages:
+    /// `ServerNotResponding` once the connection is lost and reconnecting starts,
+    /// `ReconnectFailed` once all reconnect attempts are used up.
+    pub fn try_recv(&mut self) -> Option<Msg> {
+        if let Some(msg) = self.try_reconnect() {
+            return Some(msg);
+        }
+
+        match self.recv() {
+            Some(Msg::Unknown) => self.try_recv(),
+
+            // garbage doesn't prove that the server is alive, pass it on without touching timeouts
+            Some(msg @ Msg::Malformed(..)) => Some(msg),
+
+            Some(Msg::ServerNotResponding) => {
+                self.timeouts += 1;
+
+                let lost = match self.state {
+                    ConnectionState::Connecting | ConnectionState::Connected => self.timeouts >= 3,
+                    ConnectionState::Reconnecting { .. } | ConnectionState::Failed => false,
+                };
+
+                if lost {
+                    self.state = ConnectionState::Reconnecting {
+                        attempt: 0,
+                        next_attempt: Instant::now(),
+                    };
+
+                    return Some(Msg::ServerNotResponding);
+                }
+
+                self.try_recv()
+            }
+
+            Some(msg) => {
+                self.timeouts = 0;
+                match msg {
+                    Msg::Heartbeat => {
+                        self.send(Msg::Heartbeat);
+                        self.try_recv()
+                    }
+
+                    Msg::JoinAck(..) => {
+                        self.state = ConnectionState::Connected;
+                        Some(msg)
+                    }
+
+                    // leftovers of the lost session, the world is rebuilt after JoinAck
+                    Msg::FromServer(..) if self.reconnecting() => self.try_recv(),
+
+                    _ => Some(msg)
+                }
+            }
+
+            None => None,
         }
     }
-}
 
-impl UdpCodec for ClientCodec {
-    type In = Msg;
-    type Out = Msg;
+    fn recv(&mut self) -> Option<Msg> {
+        if self.conditioner.is_idle() {
+            return self.transport.try_recv();
+        }
 
-    fn decode(&mut self, src: &SocketAddr, buf: &[u8]) -> io::Result<Self::In> {
-        if *src != self.server {
-            return Ok(Msg::Unknown);
+        while let Some(msg) = self.conditioner.pop_outgoing() {
+            self.transport.send(msg);
         }
 
-        Msg::from_bytes(buf)
+        while let Some(msg) = self.transport.try_recv() {
+            self.conditioner.push_incoming(msg);
+        }
+
+        self.conditioner.pop_incoming()
     }
 
-    fn encode(&mut self, msg: Self::Out, buf: &mut Vec<u8>) -> SocketAddr {
-        let msg = match msg {
-            Msg::JoinGame(nickname) => {
-                let payload = astero::JoinPayload { nickname };
-                payload.encode(&mut self.buf)
-                    .expect("Failed to write JoinPayload");
+    fn reconnecting(&self) -> bool {
+        match self.state {
+            ConnectionState::Reconnecting { .. } => true,
+            _ => false,
+        }
+    }
 
-                mmob::client::Msg::Join(mmob::JoinGame {
-                    payload: Some(self.buf.clone()),
-                })
+    fn try_reconnect(&mut self) -> Option<Msg> {
+        if let ConnectionState::Reconnecting { attempt, next_attempt } = self.state {
+            let now = Instant::now();
+            if now < next_attempt {
+                return None;
             }
-            Msg::LeaveGame => mmob::client::Msg::Leave(mmob::LeaveGame {}),
-            Msg::Heartbeat => mmob::client::Msg::Heartbeat(mmob::Heartbeat {}),
-            Msg::Latency(measure) => mmob::client::Msg::LatencyMeasure(measure),
-            Msg::ToServer(msg) => {
-                msg.encode(&mut self.buf);
-
-                mmob::client::Msg::Proxied(mmob::Proxied {
-                    msg: self.buf.clone()
-                })
+
+            if attempt >= network::MAX_RECONNECT_ATTEMPTS {
+                self.state = ConnectionState::Failed;
+                return Some(Msg::ReconnectFailed);
             }
 
-            Msg::Unknown |
-            Msg::ServerNotResponding |
-            Msg::JoinAck(..) |
-            Msg::FromServer(..) => unreachable!()
-        };
+            let delay = network::RECONNECT_BASE_DELAY_MS
+                .saturating_mul(1 << attempt.min(16))
+                .min(network::RECONNECT_MAX_DELAY_MS);
 
-        let msg = mmob::Client { msg: Some(msg) };
-        msg.encode(buf)
-            .expect("Failed to encode message");
+            println!("Reconnecting to server (attempt {} of {})...", attempt + 1, network::MAX_RECONNECT_ATTEMPTS);
 
-        self.buf.clear();
+            let nickname = self.nickname.clone();
+            self.send(Msg::JoinGame(nickname));
 
-        self.server
+            self.state = ConnectionState::Reconnecting {
+                attempt: attempt + 1,
+                next_attempt: now + Duration::from_millis(delay),
+            };
+        }
+
+        None
     }
 }
 
 
-pub struct Client {
-    thread_handle: Option<thread::JoinHandle<()>>,
-    to: Option<futures::sync::mpsc::UnboundedSender<Msg>>,
-    from: std::sync::mpsc::Receiver<Msg>,
-    stop: Option<futures::sync::oneshot::Sender<()>>,
-    timeouts: u32,
-}
+#[cfg(test)]
+mod tests {
+    use msg::Msg;
+    use proto::astero;
+    use resources::{
+        NetworkConditioner,
+        NetworkConditions,
+    };
+    use transport::{
+        LoopbackPeer,
+        LoopbackTransport,
+    };
 
-impl Client {
-    pub fn start() -> Self {
-        let (to_main_thread, from_client) = std::sync::mpsc::channel();
-        let (to_client, from_main_thread) = futures::sync::mpsc::unbounded();
-        let (stop_sender, stop_receiver) = futures::sync::oneshot::channel();
-
-        let thread_handle = thread::spawn(move || {
-            let mut reactor = Core::new().expect("Failed to init reactor");
-            let handle = reactor.handle();
-
-            let client_address = SocketAddr::new(IpAddr::V6(Ipv6Addr::localhost()), 0);
-            let socket =
-                UdpSocket::bind(&client_address, &handle)
-                    .expect("Failed to create socket");
-
-            let (outgoing, ingoing) =
-                socket.framed(ClientCodec::new()).split();
-
-            // Stream of timeouts. Selected with network messages.
-            // If timeout comes first it means that server is not sending any data.
-            let timeouts =
-                Interval::new(Duration::new(6, 0), &handle)
-                .expect("Failed to setup timeouts")
-                .map(|_| Msg::ServerNotResponding);
-
-            let ingoing = ingoing.select(timeouts);
-
-            let receiver = ingoing.for_each(move |msg| {
-                to_main_thread.send(msg).expect("Failed to drop message to the main thread");
-                Ok(())
-            }).map_err(|err| panic!("{}", err));
-
-            let from_main_thread = from_main_thread
-                .map_err(|_err| -> io::Error {
-                    io::ErrorKind::Other.into()
-                });
-
-            let latency_measures =
-                Interval::new(Duration::new(1, 0), &handle)
-                    .expect("Failed to setup latency measures")
-                    .map(|_| {
-                        Msg::Latency(mmob::LatencyMeasure {
-                            timestamp: cur_time_in_millis(),
-                            server_timestamp: None,
-                        })
-                    });
-
-            let sender = outgoing.send_all(from_main_thread.select(latency_measures));
-
-            let client = sender.join(receiver).select2(stop_receiver);
-            reactor.run(client).ok().expect("Client thread failure");
-        });
+    use super::{
+        Client,
+        ConnectionState,
+    };
 
-        Self {
-            thread_handle: Some(thread_handle),
-            to: Some(to_client),
-            from: from_client,
-            stop: Some(stop_sender),
-            timeouts: 0
+
+    fn join() -> (Client, LoopbackPeer) {
+        let (transport, peer) = LoopbackTransport::pair();
+        let conditioner = NetworkConditioner::new(NetworkConditions::default(), false);
+
+        let mut client = Client::new(Box::new(transport), conditioner);
+        client.join("tester".to_string());
+
+        match peer.try_recv() {
+            Some(Msg::JoinGame(ref nickname)) if nickname == "tester" => {}
+            other => panic!("Expected JoinGame, got {:?}", other),
         }
-    }
 
-    pub fn stop(&mut self) {
-        self.send(Msg::LeaveGame);
-
-        self.stop
-            .take()
-            .expect("Absent stop sender?! What?!")
-            .send(())
-            .expect("Failed to stop client reactor");
-
-        self.thread_handle
-            .take()
-            .expect("Absent thread handle?! What?")
-            .join()
-            .expect("Failed to join client thread");
+        (client, peer)
     }
 
-    pub fn send(&self, msg: Msg) {
-        self.to
-            .as_ref()
-            .and_then(|s| {
-                s.unbounded_send(msg).expect("Failed to send message to client thread");
-                Some(())
-            });
+    fn ack(client: &mut Client, peer: &LoopbackPeer) {
+        peer.send(Msg::JoinAck(astero::Player::default()));
+
+        match client.try_recv() {
+            Some(Msg::JoinAck(..)) => {}
+            other => panic!("Expected JoinAck, got {:?}", other),
+        }
+        assert_eq!(client.state(), ConnectionState::Connected);
     }
 
-    pub fn try_recv(&mut self) -> Result<Msg, std::sync::mpsc::TryRecvError> {
-        match self.from.try_recv() {
-            Ok(Msg::Unknown) => self.try_recv(),
+    #[test]
+    fn heartbeats_are_answered() {
+        let (mut client, peer) = join();
+        ack(&mut client, &peer);
 
-            Ok(Msg::ServerNotResponding) => {
-                self.timeouts += 1;
-                if self.timeouts >= 3 {
-                    return Ok(Msg::ServerNotResponding);
-                }
+        peer.send(Msg::Heartbeat);
 
-                self.try_recv()
-            }
+        assert!(client.try_recv().is_none());
+        match peer.try_recv() {
+            Some(Msg::Heartbeat) => {}
+            other => panic!("Expected Heartbeat, got {:?}", other),
+        }
+    }
 
-            Ok(msg) => {
-                self.timeouts = 0;
-                match msg {
-                    Msg::Heartbeat => {
-                        self.send(Msg::Heartbeat);
-                        self.try_recv()
-                    }
+    #[test]
+    fn reconnects_after_three_timeouts() {
+        let (mut client, peer) = join();
+        ack(&mut client, &peer);
 
-                    _ => Ok(msg)
-                }
-            }
+        peer.send(Msg::ServerNotResponding);
+        peer.send(Msg::ServerNotResponding);
+        assert!(client.try_recv().is_none());
+        assert_eq!(client.state(), ConnectionState::Connected);
+
+        peer.send(Msg::ServerNotResponding);
+        match client.try_recv() {
+            Some(Msg::ServerNotResponding) => {}
+            other => panic!("Expected ServerNotResponding, got {:?}", other),
+        }
 
-            other => other
+        // first attempt goes out right away, leftovers of the lost session are dropped
+        peer.send(Msg::FromServer(astero::server::Msg::List(Default::default())));
+        assert!(client.try_recv().is_none());
+        match peer.try_recv() {
+            Some(Msg::JoinGame(..)) => {}
+            other => panic!("Expected JoinGame, got {:?}", other),
         }
+        match client.state() {
+            ConnectionState::Reconnecting { attempt: 1, .. } => {}
+            other => panic!("Expected first reconnect attempt, got {:?}", other),
+        }
+
+        ack(&mut client, &peer);
     }
 }
@@ -0,0 +1,51 @@
+use std::time::Instant;
+
+use ggez::graphics::Point2;
+
+use constant::reconciliation;
+
+
+/// Jump of the local player caused by a server update.
+pub struct Correction {
+    // predicted position before and after replaying pending inputs on top of the server body
+    pub from: Point2,
+    pub to: Point2,
+    // distance between the server body and what was predicted for the same input
+    pub error: f32,
+    pub at: Instant,
+}
+
+
+/// How far off prediction of the local player has been.
+pub struct Corrections {
+    pub last: Option<Correction>,
+    pub mean_error: f32,
+    pub max_error: f32,
+    pub count: u64,
+}
+
+impl Corrections {
+    pub fn new() -> Self {
+        Self {
+            last: None,
+            mean_error: 0.0,
+            max_error: 0.0,
+            count: 0,
+        }
+    }
+
+    pub fn record(&mut self, correction: Correction) {
+        self.count += 1;
+        self.mean_error += (correction.error - self.mean_error) * reconciliation::ERROR_SMOOTHING;
+        self.max_error = self.max_error.max(correction.error);
+
+        if correction.error > reconciliation::LOG_THRESHOLD {
+            println!(
+                "Prediction was off by {:.1} (mean {:.1}, max {:.1})",
+                correction.error, self.mean_error, self.max_error
+            );
+        }
+
+        self.last = Some(correction);
+    }
+}
@@ -0,0 +1,11 @@
+use specs::Entity;
+
+
+pub struct DamageEvent {
+    pub entity: Entity,
+    pub amount: f32,
+}
+
+
+/// Damage taken since the last fixed step, turned into visual effects by `DamageEffects`.
+pub struct DamageEvents(pub Vec<DamageEvent>);
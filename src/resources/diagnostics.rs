@@ -0,0 +1,33 @@
+use msg::DecodeError;
+use validation::Violation;
+
+
+/// Counters of bad data received from the server.
+/// None of these is fatal: the offending packet is dropped and the game goes on.
+pub struct Diagnostics {
+    pub malformed_packets: u64,
+    pub protocol_violations: u64,
+    // updates of ids that aren't created yet or are already destroyed,
+    // usual around joins and reconnects, so they are only counted
+    pub unknown_entity_updates: u64,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self {
+            malformed_packets: 0,
+            protocol_violations: 0,
+            unknown_entity_updates: 0,
+        }
+    }
+
+    pub fn malformed_packet(&mut self, err: &DecodeError) {
+        self.malformed_packets += 1;
+        println!("Dropped malformed packet from server (#{}): {}", self.malformed_packets, err);
+    }
+
+    pub fn protocol_violation(&mut self, violation: &Violation) {
+        self.protocol_violations += 1;
+        println!("Ignored invalid server message (#{}): {}", self.protocol_violations, violation);
+    }
+}
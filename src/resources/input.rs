@@ -2,6 +2,7 @@ use std::collections::VecDeque;
 
 use ggez::event::Keycode;
 
+use components::Body;
 use proto::astero;
 
 
@@ -74,6 +75,19 @@ impl Input {
         Some(self.diff(old_input))
     }
 
+    /// Applies an input diff, the reverse of `key_down`/`key_up`.
+    pub fn apply(&mut self, update: &astero::Input) {
+        if let Some(turn) = update.turn {
+            self.turn = turn;
+        }
+        if let Some(accel) = update.accel {
+            self.accel = accel;
+        }
+        if let Some(fire) = update.fire {
+            self.fire = fire;
+        }
+    }
+
     fn diff(
         &self,
         Self {
@@ -103,6 +117,8 @@ pub struct PendingInput {
     sequence_number: u32,
     pub full_update_steps: u32,
     pub input: Input,
+    // predicted body when the input was applied (if the player existed)
+    pub body: Option<Body>,
 }
 
 pub struct InputBuffer {
@@ -118,18 +134,26 @@ impl InputBuffer {
         }
     }
 
-    pub fn add(&mut self, input: Input) -> u32 {
+    pub fn add(&mut self, input: Input, body: Option<Body>) -> u32 {
         self.sequence_number = self.sequence_number.wrapping_add(1);
 
         self.buf.push_back(PendingInput {
             sequence_number: self.sequence_number,
             full_update_steps: 0,
             input,
+            body,
         });
 
         self.sequence_number
     }
 
+    /// Same as `add`, but the input keeps the number it was recorded with,
+    /// so that the recorded acks of the server refer to it.
+    pub fn add_replayed(&mut self, sequence_number: u32, input: Input, body: Option<Body>) -> u32 {
+        self.sequence_number = sequence_number.wrapping_sub(1);
+        self.add(input, body)
+    }
+
     pub fn increase_update_step(&mut self) {
         self.buf.back_mut().and_then(|pending| {
             pending.full_update_steps += 1;
@@ -139,7 +163,7 @@ impl InputBuffer {
     }
 
     pub fn get_state_after(&mut self, sequence_number: u32) -> impl Iterator<Item=&PendingInput> {
-        while self.buf[0].sequence_number <= sequence_number {
+        while self.buf.front().map(|pending| pending.sequence_number <= sequence_number).unwrap_or(false) {
             self.buf.pop_front();
         }
 
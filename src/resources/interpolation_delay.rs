@@ -0,0 +1,66 @@
+use constant::interpolation;
+
+/// How far in the past remote entities are drawn.
+/// Follows the one-way latency plus the spacing of server updates and a margin
+/// for their measured jitter, so that an update has usually arrived by the time it is needed.
+pub struct InterpolationDelay {
+    min: f32,
+    max: f32,
+    current: f32,
+    // (server timestamp, local arrival time) of the last update
+    last: Option<(u64, u64)>,
+    interval: f32,
+    jitter: f32,
+}
+
+impl InterpolationDelay {
+    /// All in millis, `initial` is clamped to `min..max`.
+    /// With `min == max` the delay is fixed.
+    pub fn new(initial: u64, min: u64, max: u64) -> Self {
+        let min = min as f32;
+        let max = max as f32;
+        let current = (initial as f32).max(min).min(max);
+
+        Self {
+            min,
+            max,
+            current,
+            last: None,
+            interval: current,
+            jitter: 0.0,
+        }
+    }
+
+    /// `latency` is the one-way trip from the server, in millis.
+    pub fn on_update(&mut self, server_timestamp: u64, arrival: u64, latency: u64) {
+        if let Some((last_server_timestamp, last_arrival)) = self.last {
+            let interval = server_timestamp as i64 - last_server_timestamp as i64;
+            // change of transit time between two consecutive updates
+            let transit_change = (arrival as i64 - last_arrival as i64) - interval;
+
+            self.interval += (interval as f32 - self.interval) * interpolation::DELAY_SMOOTHING;
+            self.jitter += ((transit_change as f32).abs() - self.jitter) * interpolation::DELAY_SMOOTHING;
+
+            // samples are keyed by the time the server sent them, they arrive `latency` later
+            let target = (latency as f32 + self.interval + interpolation::JITTER_MARGIN * self.jitter)
+                .max(self.min)
+                .min(self.max);
+            self.current += (target - self.current) * interpolation::DELAY_ADJUSTMENT_RATE;
+        }
+
+        self.last = Some((server_timestamp, arrival));
+    }
+
+    // lost session, keep the delay but don't measure across the gap
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    pub fn current(&self) -> u64 {
+        self.current.round() as u64
+    }
+
+    pub fn jitter(&self) -> f32 {
+        self.jitter
+    }
+}
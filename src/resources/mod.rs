@@ -1,21 +1,58 @@
+mod arena;
 mod assets;
+mod camera;
 mod client;
+mod corrections;
+mod damage_events;
+mod diagnostics;
 mod input;
+mod interpolation_delay;
+mod network_conditioner;
+mod network_entity_map;
+mod predicted_contacts;
 mod server_clock;
 mod system_run_mode;
 mod unconfirmed_shot_id;
 
 
+pub use self::arena::{
+    Arena,
+    Topology,
+};
 pub use self::assets::{
     Assets,
     SpriteKind,
 };
-pub use self::client::Client;
+pub use self::camera::Camera;
+pub use self::client::{
+    Client,
+    ConnectionState,
+};
+pub use self::corrections::{
+    Correction,
+    Corrections,
+};
+pub use self::damage_events::{
+    DamageEvent,
+    DamageEvents,
+};
+pub use self::diagnostics::Diagnostics;
 pub use self::input::{
     Input,
     PendingInput,
     InputBuffer,
 };
+pub use self::interpolation_delay::InterpolationDelay;
+pub use self::network_conditioner::{
+    JitterDistribution,
+    NetworkConditioner,
+    NetworkConditions,
+};
+pub use self::network_entity_map::NetworkEntityMap;
+pub use self::predicted_contacts::{
+    ContactKind,
+    PredictedContacts,
+};
 pub use self::server_clock::ServerClock;
 pub use self::system_run_mode::{
     SystemRunMode,
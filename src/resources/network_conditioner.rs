@@ -0,0 +1,223 @@
+use std::fmt;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use rand::{
+    self,
+    Rng,
+    distributions::{
+        IndependentSample,
+        Normal,
+    },
+};
+
+use msg::Msg;
+use util::duration_to_millis;
+
+
+// upper bound of the extra delay given to a datagram picked for reordering
+const REORDER_MAX_EXTRA_DELAY_MS: u64 = 100;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JitterDistribution {
+    // anywhere in latency ± jitter
+    Uniform,
+    // jitter is the standard deviation around latency
+    Normal,
+}
+
+
+/// Link degradation applied by `NetworkConditioner` in each direction.
+#[derive(Debug, Clone)]
+pub struct NetworkConditions {
+    // one-way delay
+    pub latency: Duration,
+    pub jitter: Duration,
+    pub jitter_distribution: JitterDistribution,
+    // fractions of datagrams, 0.0..1.0
+    pub loss: f32,
+    pub duplication: f32,
+    pub reordering: f32,
+}
+
+impl Default for NetworkConditions {
+    fn default() -> Self {
+        Self {
+            latency: Duration::from_millis(100),
+            jitter: Duration::from_millis(20),
+            jitter_distribution: JitterDistribution::Uniform,
+            loss: 0.05,
+            duplication: 0.01,
+            reordering: 0.05,
+        }
+    }
+}
+
+impl fmt::Display for NetworkConditions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "latency {} ms, jitter {} ms ({:?}), loss {:.0}%, duplication {:.0}%, reordering {:.0}%",
+            duration_to_millis(self.latency), duration_to_millis(self.jitter), self.jitter_distribution,
+            self.loss * 100.0, self.duplication * 100.0, self.reordering * 100.0
+        )
+    }
+}
+
+
+struct Delayed {
+    deliver_at: Instant,
+    msg: Msg,
+}
+
+
+/// Simulates a bad link inside `Client`: every datagram sent or received
+/// is delayed, dropped, duplicated or reordered according to `NetworkConditions`.
+/// Can be switched on and off at runtime, messages already held back are still delivered.
+pub struct NetworkConditioner {
+    conditions: NetworkConditions,
+    enabled: bool,
+    incoming: Vec<Delayed>,
+    outgoing: Vec<Delayed>,
+}
+
+impl NetworkConditioner {
+    pub fn new(conditions: NetworkConditions, enabled: bool) -> Self {
+        Self {
+            conditions,
+            enabled,
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+
+        if self.enabled {
+            println!("Network conditioner on: {}", self.conditions);
+        } else {
+            println!("Network conditioner off");
+        }
+    }
+
+    // nothing is held back, messages can bypass the queues
+    pub fn is_idle(&self) -> bool {
+        !self.enabled && self.incoming.is_empty() && self.outgoing.is_empty()
+    }
+
+    pub fn push_incoming(&mut self, msg: Msg) {
+        let now = Instant::now();
+
+        match msg {
+            // produced locally, not by the link
+            Msg::ServerNotResponding | Msg::ReconnectFailed | Msg::ReplayedInput(..) => {
+                self.incoming.push(Delayed { deliver_at: now, msg });
+            }
+            _ => {
+                let incoming = &mut self.incoming;
+                condition(&self.conditions, self.enabled, now, msg, |delayed| incoming.push(delayed));
+            }
+        }
+    }
+
+    pub fn pop_incoming(&mut self) -> Option<Msg> {
+        pop_due(&mut self.incoming, Instant::now())
+    }
+
+    pub fn push_outgoing(&mut self, msg: Msg) {
+        let outgoing = &mut self.outgoing;
+        condition(&self.conditions, self.enabled, Instant::now(), msg, |delayed| outgoing.push(delayed));
+    }
+
+    pub fn pop_outgoing(&mut self) -> Option<Msg> {
+        pop_due(&mut self.outgoing, Instant::now())
+    }
+
+    // held back messages are lost on shutdown or reconnect
+    pub fn clear(&mut self) {
+        self.incoming.clear();
+        self.outgoing.clear();
+    }
+}
+
+
+fn condition<F>(conditions: &NetworkConditions, enabled: bool, now: Instant, msg: Msg, mut push: F)
+    where F: FnMut(Delayed)
+{
+    if !enabled {
+        push(Delayed { deliver_at: now, msg });
+        return;
+    }
+
+    if rand::random::<f32>() < conditions.loss {
+        return;
+    }
+
+    if rand::random::<f32>() < conditions.duplication {
+        if let Some(copy) = duplicate(&msg) {
+            push(Delayed { deliver_at: now + delay(conditions), msg: copy });
+        }
+    }
+
+    push(Delayed { deliver_at: now + delay(conditions), msg });
+}
+
+fn delay(conditions: &NetworkConditions) -> Duration {
+    let mut rng = rand::thread_rng();
+
+    let latency = duration_to_millis(conditions.latency) as f64;
+    let jitter = duration_to_millis(conditions.jitter) as f64;
+
+    let delay = if jitter > 0.0 {
+        match conditions.jitter_distribution {
+            JitterDistribution::Uniform => latency + rng.gen_range(-jitter, jitter),
+            JitterDistribution::Normal => Normal::new(latency, jitter).ind_sample(&mut rng),
+        }
+    } else {
+        latency
+    };
+
+    let mut delay = Duration::from_millis(delay.max(0.0) as u64);
+
+    if rand::random::<f32>() < conditions.reordering {
+        // held back long enough for the following datagrams to overtake it
+        delay += Duration::from_millis(rng.gen_range(1, REORDER_MAX_EXTRA_DELAY_MS));
+    }
+
+    delay
+}
+
+fn pop_due(queue: &mut Vec<Delayed>, now: Instant) -> Option<Msg> {
+    let due = queue.iter()
+        .enumerate()
+        .filter(|&(_, delayed)| delayed.deliver_at <= now)
+        .min_by_key(|&(_, delayed)| delayed.deliver_at)
+        .map(|(idx, _)| idx);
+
+    due.map(|idx| queue.remove(idx).msg)
+}
+
+// Msg isn't Clone because of decode errors, those are never duplicated
+fn duplicate(msg: &Msg) -> Option<Msg> {
+    let copy = match *msg {
+        Msg::Unknown => Msg::Unknown,
+        Msg::JoinGame(ref nickname) => Msg::JoinGame(nickname.clone()),
+        Msg::JoinAck(ref player) => Msg::JoinAck(player.clone()),
+        Msg::LeaveGame => Msg::LeaveGame,
+        Msg::Heartbeat => Msg::Heartbeat,
+        Msg::Latency(ref measure) => Msg::Latency(measure.clone()),
+        Msg::ToServer(ref msg) => Msg::ToServer(msg.clone()),
+        Msg::FromServer(ref msg) => Msg::FromServer(msg.clone()),
+
+        Msg::Malformed(..) |
+        Msg::ServerNotResponding |
+        Msg::ReconnectFailed |
+        Msg::ReplayedInput(..) => return None,
+    };
+
+    Some(copy)
+}
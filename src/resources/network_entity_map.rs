@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use specs::Entity;
+
+
+/// Index from server ids to local entities, the other way is the entity's `NetworkId`.
+/// Maintained on create and destroy of every entity with a `NetworkId`.
+pub struct NetworkEntityMap {
+    by_id: HashMap<u32, Entity>,
+}
+
+impl NetworkEntityMap {
+    pub fn new() -> Self {
+        Self {
+            by_id: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, id: u32, entity: Entity) {
+        self.by_id.insert(id, entity);
+    }
+
+    pub fn remove(&mut self, id: u32) -> Option<Entity> {
+        self.by_id.remove(&id)
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.by_id.contains_key(&id)
+    }
+
+    pub fn get_entity(&self, id: u32) -> Option<Entity> {
+        self.by_id.get(&id).cloned()
+    }
+
+    pub fn clear(&mut self) {
+        self.by_id.clear();
+    }
+}
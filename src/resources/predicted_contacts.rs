@@ -0,0 +1,149 @@
+use specs::Entity;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContactKind {
+    // shot is hidden until the server decides
+    Shot,
+    Ship,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct PredictedContact {
+    pub kind: ContactKind,
+    // shot or ship that ran into `target`
+    pub by: Entity,
+    pub target: Entity,
+    // local time in ms after which an unconfirmed contact is rolled back
+    pub deadline: u64,
+    pub confirmed: bool,
+}
+
+
+/// Contacts the client has seen before the server.
+/// Their effects are played right away and kept or undone once the server
+/// reports damage to (or destroys) the entity that was hit.
+pub struct PredictedContacts {
+    contacts: Vec<PredictedContact>,
+    pub confirmed: u32,
+    pub rolled_back: u32,
+}
+
+impl PredictedContacts {
+    pub fn new() -> Self {
+        Self {
+            contacts: Vec::new(),
+            confirmed: 0,
+            rolled_back: 0,
+        }
+    }
+
+    pub fn add(&mut self, kind: ContactKind, by: Entity, target: Entity, deadline: u64) {
+        self.contacts.push(PredictedContact {
+            kind,
+            by,
+            target,
+            deadline,
+            confirmed: false,
+        });
+    }
+
+    /// Confirmed contacts are kept until their deadline too,
+    /// so that bodies still touching don't count as a new contact.
+    pub fn contains(&self, by: Entity, target: Entity) -> bool {
+        self.contacts.iter()
+            .any(|contact| contact.by == by && contact.target == target)
+    }
+
+    /// Server damaged `entity`, that is one hit: the oldest unconfirmed contact
+    /// of a shot with it, or of it as a ship, happened.
+    /// Server shots have no id, so the hit can't be tied to a particular shot.
+    /// Returns the shot spent on it.
+    pub fn confirm(&mut self, entity: Entity) -> Option<Entity> {
+        let contact = self.contacts.iter_mut()
+            .find(|contact| !contact.confirmed && match contact.kind {
+                ContactKind::Shot => contact.target == entity,
+                ContactKind::Ship => contact.by == entity,
+            })?;
+
+        contact.confirmed = true;
+        self.confirmed += 1;
+
+        match contact.kind {
+            ContactKind::Shot => Some(contact.by),
+            ContactKind::Ship => None,
+        }
+    }
+
+    /// Forgets contacts past their deadline and returns the ones the server never confirmed.
+    pub fn expire(&mut self, now: u64) -> Vec<PredictedContact> {
+        let (expired, pending): (Vec<_>, Vec<_>) = self.contacts.drain(..)
+            .partition(|contact| contact.deadline <= now);
+        self.contacts = pending;
+
+        let rolled_back: Vec<_> = expired.into_iter()
+            .filter(|contact| !contact.confirmed)
+            .collect();
+        self.rolled_back += rolled_back.len() as u32;
+
+        rolled_back
+    }
+
+    pub fn pending(&self) -> usize {
+        self.contacts.iter()
+            .filter(|contact| !contact.confirmed)
+            .count()
+    }
+
+    pub fn clear(&mut self) {
+        self.contacts.clear();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use specs::World;
+
+    use super::{
+        ContactKind,
+        PredictedContacts,
+    };
+
+    #[test]
+    fn one_hit_confirms_one_contact() {
+        let mut world = World::new();
+        let first_shot = world.create_entity().build();
+        let second_shot = world.create_entity().build();
+        let asteroid = world.create_entity().build();
+
+        let mut contacts = PredictedContacts::new();
+        contacts.add(ContactKind::Shot, first_shot, asteroid, 100);
+        contacts.add(ContactKind::Shot, second_shot, asteroid, 100);
+
+        // damage to the shot itself isn't a hit on the asteroid
+        assert_eq!(contacts.confirm(first_shot), None);
+        assert_eq!(contacts.confirm(asteroid), Some(first_shot));
+        assert_eq!(contacts.pending(), 1);
+
+        let rolled_back = contacts.expire(100);
+        assert_eq!(rolled_back.len(), 1);
+        assert_eq!(rolled_back[0].by, second_shot);
+    }
+
+    #[test]
+    fn ship_contact_is_confirmed_by_damage_to_the_ship() {
+        let mut world = World::new();
+        let ship = world.create_entity().build();
+        let asteroid = world.create_entity().build();
+
+        let mut contacts = PredictedContacts::new();
+        contacts.add(ContactKind::Ship, ship, asteroid, 100);
+
+        assert_eq!(contacts.confirm(asteroid), None);
+        assert_eq!(contacts.pending(), 1);
+        assert_eq!(contacts.confirm(ship), None);
+        assert_eq!(contacts.pending(), 0);
+    }
+}
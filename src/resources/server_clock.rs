@@ -1,27 +1,316 @@
+use std::collections::VecDeque;
+
 use util::cur_time_in_millis;
 
 
+// latency measures kept for filtering
+const WINDOW: usize = 16;
+// samples slower than the fastest one by more than this are ignored, in millis
+const MAX_RTT_EXCESS: i64 = 20;
+// samples this many median absolute deviations from the median offset are outliers
+const MAX_OFFSET_DEVIATIONS: i64 = 3;
+// weight of a new estimate in the smoothed offset
+const SMOOTHING: f64 = 0.25;
+// real clocks drift by tens of ppm, anything above this is noise in a short window
+const MAX_DRIFT: f64 = 500.0 / 1_000_000.0;
+
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    // local time the measure was answered
+    local: i64,
+    // server clock minus local clock
+    offset: i64,
+    rtt: i64,
+}
+
+
+/// Offset between the server clock and the local one, estimated the way NTP does it:
+/// from a rolling window of latency measures only the fastest round trips are trusted,
+/// offsets far from the rest are dropped, what's left is smoothed and the drift
+/// between the clocks is tracked so the offset stays right between measures.
 pub struct ServerClock {
-    latency: i32,
-    delta_time: i32,
+    samples: VecDeque<Sample>,
+    // smoothed offset at local time `reference`
+    offset: Option<f64>,
+    reference: i64,
+    // change of the offset per local millisecond
+    drift: f64,
+    rtt: i64,
 }
 
 impl ServerClock {
     pub fn new() -> Self {
         Self {
-            latency: 0,
-            delta_time: 0,
+            samples: VecDeque::with_capacity(WINDOW),
+            offset: None,
+            reference: 0,
+            drift: 0.0,
+            rtt: 0,
         }
     }
 
+    /// `then` is the local time the measure was sent at,
+    /// `server_timestamp` the server time it was answered at.
     pub fn update(&mut self, then: u64, server_timestamp: u64) {
-        let now = cur_time_in_millis();
+        self.update_at(cur_time_in_millis(), then, server_timestamp);
+    }
+
+    fn update_at(&mut self, now: u64, then: u64, server_timestamp: u64) {
+        let now = now as i64;
+        let then = then as i64;
+
+        let rtt = now - then;
+        if rtt < 0 {
+            // answer to a measure from the future, nothing to learn from it
+            return;
+        }
+
+        if self.samples.len() == WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample {
+            local: now,
+            // the server answered halfway through the round trip
+            offset: server_timestamp as i64 - (then + rtt / 2),
+            rtt,
+        });
 
-        self.delta_time = (server_timestamp - now) as i32;
-        self.latency = ((now - then) / 2) as i32;
+        let trusted = self.trusted_samples();
+        let best = match trusted.iter().min_by_key(|sample| sample.rtt) {
+            Some(best) => *best,
+            None => return,
+        };
+
+        self.rtt = best.rtt;
+        self.drift = drift(&trusted).max(-MAX_DRIFT).min(MAX_DRIFT);
+
+        // mean of the trusted offsets, carried forward to now
+        let n = trusted.len() as f64;
+        let mean_local = trusted.iter().map(|sample| sample.local as f64).sum::<f64>() / n;
+        let mean_offset = trusted.iter().map(|sample| sample.offset as f64).sum::<f64>() / n;
+        let estimate = mean_offset + self.drift * (now as f64 - mean_local);
+
+        self.offset = Some(match self.offset {
+            Some(_) => {
+                let predicted = self.offset_at(now);
+                predicted + (estimate - predicted) * SMOOTHING
+            }
+            None => estimate,
+        });
+        self.reference = now;
     }
 
+    /// Server clock minus local clock, in millis.
     pub fn compensation(&self) -> i32 {
-        self.latency + self.delta_time
+        self.offset_at(cur_time_in_millis() as i64).round() as i32
+    }
+
+    /// Round trip time of the fastest recent measure, in millis.
+    pub fn rtt(&self) -> u64 {
+        self.rtt as u64
+    }
+
+    /// Whether there was a latency measure to estimate the offset from.
+    pub fn is_synced(&self) -> bool {
+        self.offset.is_some()
+    }
+
+    /// Local time at which the server clock showed `server_timestamp`,
+    /// `None` until the first latency measure.
+    pub fn to_local(&self, server_timestamp: u64) -> Option<u64> {
+        self.to_local_at(cur_time_in_millis(), server_timestamp)
+    }
+
+    fn to_local_at(&self, now: u64, server_timestamp: u64) -> Option<u64> {
+        if self.offset.is_none() {
+            return None;
+        }
+
+        let local = server_timestamp as i64 - self.offset_at(now as i64).round() as i64;
+
+        Some(local.max(0) as u64)
+    }
+
+    fn offset_at(&self, local: i64) -> f64 {
+        match self.offset {
+            Some(offset) => offset + self.drift * (local - self.reference) as f64,
+            None => 0.0,
+        }
+    }
+
+    // fast round trips whose offsets agree with each other
+    fn trusted_samples(&self) -> Vec<Sample> {
+        let min_rtt = match self.samples.iter().map(|sample| sample.rtt).min() {
+            Some(min_rtt) => min_rtt,
+            None => return Vec::new(),
+        };
+
+        let fast: Vec<Sample> = self.samples.iter()
+            .filter(|sample| sample.rtt <= min_rtt + MAX_RTT_EXCESS)
+            .cloned()
+            .collect();
+
+        let median_offset = median(fast.iter().map(|sample| sample.offset).collect());
+        let deviation = median(fast.iter().map(|sample| (sample.offset - median_offset).abs()).collect());
+
+        // with no spread at all a tiny deviation would reject everything else
+        let max_deviation = (MAX_OFFSET_DEVIATIONS * deviation).max(1);
+
+        fast.into_iter()
+            .filter(|sample| (sample.offset - median_offset).abs() <= max_deviation)
+            .collect()
     }
-}
\ No newline at end of file
+}
+
+
+fn median(mut values: Vec<i64>) -> i64 {
+    if values.is_empty() {
+        return 0;
+    }
+
+    values.sort();
+    values[values.len() / 2]
+}
+
+// least squares slope of offset over local time
+fn drift(samples: &[Sample]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+
+    let n = samples.len() as f64;
+    let mean_local = samples.iter().map(|sample| sample.local as f64).sum::<f64>() / n;
+    let mean_offset = samples.iter().map(|sample| sample.offset as f64).sum::<f64>() / n;
+
+    let (covariance, variance) = samples.iter()
+        .fold((0.0, 0.0), |(covariance, variance), sample| {
+            let dl = sample.local as f64 - mean_local;
+            let doff = sample.offset as f64 - mean_offset;
+
+            (covariance + dl * doff, variance + dl * dl)
+        });
+
+    if variance > 0.0 {
+        covariance / variance
+    } else {
+        0.0
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        MAX_DRIFT,
+        ServerClock,
+        WINDOW,
+    };
+
+
+    // local time of the first measure, far enough from zero for negative offsets
+    const START: u64 = 1_000_000;
+
+    // Measure sent at local time `sent` that took `rtt` millis,
+    // answered by a server whose clock is `offset` millis ahead.
+    fn measure(clock: &mut ServerClock, sent: u64, rtt: u64, offset: i64) {
+        let server_timestamp = (sent + rtt / 2) as i64 + offset;
+        clock.update_at(sent + rtt, sent, server_timestamp as u64);
+    }
+
+    fn assert_close(actual: f64, expected: f64, epsilon: f64) {
+        assert!((actual - expected).abs() < epsilon, "{} is not {}", actual, expected);
+    }
+
+    #[test]
+    fn unsynced_clock_has_no_local_time() {
+        let clock = ServerClock::new();
+
+        assert!(!clock.is_synced());
+        assert_eq!(clock.to_local_at(START, START), None);
+    }
+
+    #[test]
+    fn slow_round_trips_are_ignored() {
+        let mut clock = ServerClock::new();
+
+        for i in 0..8 {
+            measure(&mut clock, START + i * 1000, 10, 100);
+        }
+        // asymmetric route: the answer took much longer to come back
+        measure(&mut clock, START + 8000, 300, 250);
+        measure(&mut clock, START + 9000, 200, -50);
+
+        assert_eq!(clock.rtt(), 10);
+        assert_close(clock.offset_at(clock.reference), 100.0, 0.5);
+    }
+
+    #[test]
+    fn outlying_offsets_are_ignored() {
+        let mut clock = ServerClock::new();
+
+        for i in 0..8 {
+            measure(&mut clock, START + i * 1000, 10, 100);
+        }
+        measure(&mut clock, START + 8000, 10, 400);
+
+        assert_close(clock.offset_at(clock.reference), 100.0, 0.5);
+    }
+
+    #[test]
+    fn fastest_measure_in_the_window_gives_the_rtt() {
+        let mut clock = ServerClock::new();
+
+        measure(&mut clock, START, 10, 100);
+        for i in 1..WINDOW as u64 {
+            measure(&mut clock, START + i * 1000, 50, 100);
+        }
+        assert_eq!(clock.rtt(), 10);
+
+        // the fast one leaves the window
+        measure(&mut clock, START + WINDOW as u64 * 1000, 50, 100);
+        assert_eq!(clock.rtt(), 50);
+    }
+
+    #[test]
+    fn server_clock_behind_the_local_one() {
+        let mut clock = ServerClock::new();
+
+        for i in 0..4 {
+            measure(&mut clock, START + i * 1000, 20, -5000);
+        }
+
+        assert!(clock.is_synced());
+        assert_close(clock.offset_at(clock.reference), -5000.0, 0.5);
+        assert_eq!(clock.to_local_at(clock.reference as u64, START), Some(START + 5000));
+        // server time from before the local clock started
+        assert_eq!(clock.to_local_at(clock.reference as u64, 0), Some(5000));
+    }
+
+    #[test]
+    fn drift_between_clocks_is_estimated() {
+        let mut clock = ServerClock::new();
+
+        // server clock gains 10 ms every 100 s, 100 ppm
+        for i in 0..WINDOW as u64 {
+            measure(&mut clock, START + i * 100_000, 10, 10 * i as i64);
+        }
+
+        assert_close(clock.drift, 100.0 / 1_000_000.0, 1e-9);
+    }
+
+    #[test]
+    fn drift_is_clamped() {
+        let mut gaining = ServerClock::new();
+        let mut losing = ServerClock::new();
+
+        // 10000 ppm either way
+        for i in 0..WINDOW as u64 {
+            measure(&mut gaining, START + i * 100_000, 10, 1000 * i as i64);
+            measure(&mut losing, START + i * 100_000, 10, -1000 * i as i64);
+        }
+
+        assert_close(gaining.drift, MAX_DRIFT, 1e-12);
+        assert_close(losing.drift, -MAX_DRIFT, 1e-12);
+    }
+}
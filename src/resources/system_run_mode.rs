@@ -1,6 +1,5 @@
 pub enum SystemRunMode {
     Prediction,
-    Reconciliation,
     // (render_timestamp, blending_factor)
     Interpolation(u64, f32),
 }
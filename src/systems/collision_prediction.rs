@@ -0,0 +1,108 @@
+use specs;
+
+use collision::{
+    self,
+    SpatialHash,
+};
+use constant;
+use components;
+use resources::{
+    self,
+    ContactKind,
+    SpriteKind,
+};
+use util;
+
+
+/// Finds the local player's shots and ship running into asteroids before the server reports it.
+/// Hit shots are hidden and both sides flash; shots come back if the server never confirms the hit.
+pub struct CollisionPrediction;
+
+impl<'a> specs::System<'a> for CollisionPrediction {
+    #[cfg_attr(feature="clippy", allow(type_complexity))]
+    type SystemData = (
+        specs::Fetch<'a, resources::CurrentSystemRunMode>,
+        specs::Fetch<'a, resources::Arena>,
+        specs::Fetch<'a, resources::ServerClock>,
+        specs::Fetch<'a, resources::InterpolationDelay>,
+        specs::FetchMut<'a, resources::PredictedContacts>,
+        specs::Entities<'a>,
+        specs::ReadStorage<'a, components::Body>,
+        specs::ReadStorage<'a, components::Controllable>,
+        specs::ReadStorage<'a, components::PredictedShot>,
+        specs::WriteStorage<'a, components::Sprite>,
+        specs::WriteStorage<'a, components::HitFlash>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            run_mode, arena, server_clock, interpolation_delay, mut contacts,
+            entities,
+            bodies, controllables, predicted_shots, mut sprites, mut hit_flashes,
+        ): Self::SystemData
+    ) {
+        use specs::Join;
+
+        if let resources::SystemRunMode::Prediction = run_mode.0 {
+            let now = util::cur_time_in_millis();
+
+            for contact in contacts.expire(now) {
+                if contact.kind == ContactKind::Shot && entities.is_alive(contact.by) {
+                    sprites.insert(contact.by, components::Sprite(SpriteKind::Shot));
+                }
+            }
+
+            // asteroids are drawn in the past, the server needs that long and a round trip to answer
+            let deadline = now + server_clock.rtt() + interpolation_delay.current() +
+                constant::collision::CONFIRM_MARGIN_MS;
+
+            let mut asteroids = SpatialHash::new(constant::collision::CELL_SIZE);
+            for (entity, body, sprite) in (&*entities, &bodies, &sprites).join() {
+                if sprite.0 == SpriteKind::Asteroid {
+                    asteroids.insert(entity, body.pos, body.size / 2.0);
+                }
+            }
+
+            let new_contacts = {
+                // first asteroid `body` touches that it isn't already known to touch
+                let find_contact = |by: specs::Entity, body: &components::Body| {
+                    arena.images(body.pos).into_iter()
+                        .flat_map(|pos| asteroids.query(pos, body.size / 2.0))
+                        .filter(|&asteroid| !contacts.contains(by, asteroid))
+                        .find(|&asteroid| match bodies.get(asteroid) {
+                            Some(asteroid_body) => collision::overlap(body, asteroid_body, &arena),
+                            None => false,
+                        })
+                };
+
+                let mut new_contacts = Vec::new();
+
+                // other players' shots are up to the server, shots already hidden have their contact
+                for (entity, body, _, _) in (&*entities, &bodies, &sprites, &predicted_shots).join() {
+                    if let Some(asteroid) = find_contact(entity, body) {
+                        new_contacts.push((ContactKind::Shot, entity, asteroid));
+                    }
+                }
+
+                for (entity, body, _) in (&*entities, &bodies, &controllables).join() {
+                    if let Some(asteroid) = find_contact(entity, body) {
+                        new_contacts.push((ContactKind::Ship, entity, asteroid));
+                    }
+                }
+
+                new_contacts
+            };
+
+            for (kind, by, target) in new_contacts {
+                contacts.add(kind, by, target, deadline);
+
+                hit_flashes.insert(target, components::HitFlash::new());
+                match kind {
+                    ContactKind::Shot => { sprites.remove(by); }
+                    ContactKind::Ship => { hit_flashes.insert(by, components::HitFlash::new()); }
+                }
+            }
+        }
+    }
+}
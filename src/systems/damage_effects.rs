@@ -0,0 +1,76 @@
+use specs;
+
+use ggez::graphics::Vector2;
+
+use constant::{
+    hud,
+    physics,
+};
+use components;
+use resources;
+
+
+/// Turns damage events into hit flashes and floating numbers
+/// and lets the recent damage trails on health bars catch up.
+pub struct DamageEffects;
+
+impl<'a> specs::System<'a> for DamageEffects {
+    #[allow(type_complexity)]
+    type SystemData = (
+        specs::Fetch<'a, resources::CurrentSystemRunMode>,
+        specs::FetchMut<'a, resources::DamageEvents>,
+        specs::Entities<'a>,
+        specs::WriteStorage<'a, components::Body>,
+        specs::WriteStorage<'a, components::Life>,
+        specs::WriteStorage<'a, components::HitFlash>,
+        specs::WriteStorage<'a, components::DamageNumber>,
+        specs::WriteStorage<'a, components::TimeToLive>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            run_mode, mut damage_events,
+            entities,
+            mut bodies, mut lives, mut hit_flashes, mut damage_numbers, mut ttls,
+        ): Self::SystemData
+    ) {
+        use specs::Join;
+
+        if let resources::SystemRunMode::Prediction = run_mode.0 {
+            for event in damage_events.0.drain(..) {
+                let pos = match bodies.get(event.entity) {
+                    Some(body) => body.pos + Vector2::new(0.0, body.size / 2.0),
+                    // destroyed in the meantime
+                    None => continue,
+                };
+
+                hit_flashes.insert(event.entity, components::HitFlash::new());
+
+                let number = entities.create();
+                bodies.insert(number, components::Body {
+                    pos,
+                    vel: Vector2::new(0.0, hud::DAMAGE_NUMBER_SPEED),
+                    ..Default::default()
+                });
+                damage_numbers.insert(number, components::DamageNumber::new(event.amount));
+                ttls.insert(number, components::TimeToLive::new(hud::DAMAGE_NUMBER_TTL));
+            }
+
+            let mut over = Vec::new();
+            for (entity, hit_flash) in (&*entities, &mut hit_flashes).join() {
+                hit_flash.update(physics::DELTA_TIME);
+                if hit_flash.is_over() {
+                    over.push(entity);
+                }
+            }
+            for entity in over {
+                hit_flashes.remove(entity);
+            }
+
+            for (life, ) in (&mut lives, ).join() {
+                life.update_trail(physics::DELTA_TIME);
+            }
+        }
+    }
+}
@@ -12,15 +12,18 @@ impl<'a> specs::System<'a> for KinematicsPrediction {
     type SystemData = (
         specs::Fetch<'a, resources::CurrentSystemRunMode>,
         specs::Fetch<'a, resources::Input>,
+        specs::Fetch<'a, resources::Arena>,
         specs::WriteStorage<'a, components::Body>,
         specs::WriteStorage<'a, components::BlenderBody>,
         specs::ReadStorage<'a, components::Controllable>,
         specs::ReadStorage<'a, components::Accelerator>,
     );
 
-    fn run(&mut self, (run_mode, input, mut bodies, mut blend_bodies, controllable, accelerators): Self::SystemData) {
-        if let resources::SystemRunMode::Interpolation(..) = run_mode.0 {
-            return;
+    fn run(&mut self, (run_mode, input, arena, mut bodies, mut blend_bodies, controllable, accelerators): Self::SystemData) {
+        // reconciliation replays movement with `predict_movement` directly
+        match run_mode.0 {
+            resources::SystemRunMode::Prediction => {}
+            _ => return,
         }
 
         use specs::Join;
@@ -28,12 +31,26 @@ impl<'a> specs::System<'a> for KinematicsPrediction {
         for (_cntrl, body, blend_body, accel) in (&controllable, &mut bodies, &mut blend_bodies, &accelerators).join() {
             blend_body.save(body.clone());
 
-            body.accelerate(physics::DELTA_TIME, input.accel, accel.accel, accel.decel);
-
-            body.update_position(physics::DELTA_TIME);
-            body.rotate(physics::DELTA_TIME, input.turn);
-            // TODO: move these literals to resource
-            body.wrap_position(400.0, 300.0);
+            *body = predict_movement(body, &input, accel, &arena);
         }
     }
 }
+
+
+/// One fixed step of the local player's movement under `input`.
+pub fn predict_movement(
+    body: &components::Body,
+    input: &resources::Input,
+    accel: &components::Accelerator,
+    arena: &resources::Arena
+) -> components::Body {
+    let mut body = body.clone();
+
+    body.accelerate(physics::DELTA_TIME, input.accel, accel.accel, accel.decel);
+
+    body.update_position(physics::DELTA_TIME);
+    body.rotate(physics::DELTA_TIME, input.turn);
+    arena.confine(&mut body);
+
+    body
+}
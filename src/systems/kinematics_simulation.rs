@@ -0,0 +1,31 @@
+use specs;
+
+use constant::physics;
+use components;
+use resources;
+
+
+/// Moves entities that are neither predicted nor interpolated (shots)
+/// along their velocity, the server only tells where they started.
+pub struct KinematicsSimulation;
+
+impl<'a> specs::System<'a> for KinematicsSimulation {
+    type SystemData = (
+        specs::Fetch<'a, resources::CurrentSystemRunMode>,
+        specs::Fetch<'a, resources::Arena>,
+        specs::WriteStorage<'a, components::Body>,
+        specs::ReadStorage<'a, components::Controllable>,
+        specs::ReadStorage<'a, components::InterpolationBuffer>,
+    );
+
+    fn run(&mut self, (run_mode, arena, mut bodies, controllable, interp_buffers): Self::SystemData) {
+        use specs::Join;
+
+        if let resources::SystemRunMode::Prediction = run_mode.0 {
+            for (body, (), ()) in (&mut bodies, !&controllable, !&interp_buffers).join() {
+                body.update_position(physics::DELTA_TIME);
+                arena.confine(body);
+            }
+        }
+    }
+}
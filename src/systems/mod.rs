@@ -1,9 +1,22 @@
+mod collision_prediction;
+mod damage_effects;
 mod interpolation;
 mod kinematics_prediction;
+mod kinematics_simulation;
 mod shooting;
+mod time_to_live_expiry;
 mod unconfirmed_shot_cleanup;
+mod visual_offset_decay;
 
+pub use self::collision_prediction::CollisionPrediction;
+pub use self::damage_effects::DamageEffects;
 pub use self::interpolation::Interpolation;
-pub use self::kinematics_prediction::KinematicsPrediction;
+pub use self::kinematics_prediction::{
+    KinematicsPrediction,
+    predict_movement,
+};
+pub use self::kinematics_simulation::KinematicsSimulation;
 pub use self::shooting::Shooting;
+pub use self::time_to_live_expiry::TimeToLiveExpiry;
 pub use self::unconfirmed_shot_cleanup::UnconfirmedShotCleanup;
+pub use self::visual_offset_decay::VisualOffsetDecay;
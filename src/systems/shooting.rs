@@ -32,6 +32,7 @@ impl<'a> specs::System<'a> for Shooting {
         specs::WriteStorage<'a, components::Sprite>,
         specs::WriteStorage<'a, components::TimeToLive>,
         specs::WriteStorage<'a, components::ShotNetworkId>,
+        specs::WriteStorage<'a, components::PredictedShot>,
     );
 
     fn run(
@@ -39,7 +40,7 @@ impl<'a> specs::System<'a> for Shooting {
         (
             run_mode, input, unconfirmed_shot_id,
             entities,
-            mut bodies, mut cannons, mut sprites, mut ttls, mut shot_network_ids,
+            mut bodies, mut cannons, mut sprites, mut ttls, mut shot_network_ids, mut predicted_shots,
         ): Self::SystemData
     ) {
         use specs::Join;
@@ -58,7 +59,9 @@ impl<'a> specs::System<'a> for Shooting {
                             .expect("Cannon attached to entity without body?!")
                             .clone()
                     };
-                    bodies.insert(shot, self.create_shot_body(body));
+                    let shot_body = self.create_shot_body(body);
+                    predicted_shots.insert(shot, components::PredictedShot::new(shot_body.clone()));
+                    bodies.insert(shot, shot_body);
 
                     if let Some(shot_id) = unconfirmed_shot_id.0 {
                         shot_network_ids.insert(shot, components::ShotNetworkId(shot_id, true));
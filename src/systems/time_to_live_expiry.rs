@@ -0,0 +1,32 @@
+use specs;
+
+use constant::physics;
+use components;
+use resources;
+
+
+/// Counts down `TimeToLive` and deletes entities whose time is up.
+pub struct TimeToLiveExpiry;
+
+impl<'a> specs::System<'a> for TimeToLiveExpiry {
+    type SystemData = (
+        specs::Fetch<'a, resources::CurrentSystemRunMode>,
+        specs::Entities<'a>,
+        specs::WriteStorage<'a, components::TimeToLive>,
+    );
+
+    fn run(&mut self, (run_mode, entities, mut ttls): Self::SystemData) {
+        use specs::Join;
+
+        if let resources::SystemRunMode::Prediction = run_mode.0 {
+            for (entity, ttl) in (&*entities, &mut ttls).join() {
+                ttl.update(physics::DELTA_TIME);
+
+                if ttl.expired() {
+                    // the server may have destroyed it in the same frame, that's fine
+                    let _ = entities.delete(entity);
+                }
+            }
+        }
+    }
+}
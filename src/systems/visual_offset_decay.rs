@@ -0,0 +1,25 @@
+use specs;
+
+use constant::physics;
+use components;
+use resources;
+
+
+pub struct VisualOffsetDecay;
+
+impl<'a> specs::System<'a> for VisualOffsetDecay {
+    type SystemData = (
+        specs::Fetch<'a, resources::CurrentSystemRunMode>,
+        specs::WriteStorage<'a, components::VisualOffset>,
+    );
+
+    fn run(&mut self, (run_mode, mut offsets): Self::SystemData) {
+        use specs::Join;
+
+        if let resources::SystemRunMode::Prediction = run_mode.0 {
+            for (offset, ) in (&mut offsets, ).join() {
+                offset.decay(physics::DELTA_TIME);
+            }
+        }
+    }
+}
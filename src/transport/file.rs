@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{
+    self,
+    BufReader,
+};
+use std::time::Instant;
+
+use msg::Msg;
+use proto::astero;
+use transport::{
+    recording::{
+        self,
+        Record,
+    },
+    Transport,
+};
+use util;
+
+
+use prost::Message;
+
+
+/// Plays back a recording made with `RecordingTransport`, keeping the original
+/// spacing in time. Recorded inputs are handed to the game as `Msg::ReplayedInput`,
+/// whatever the client sends is dropped.
+pub struct FileTransport {
+    records: VecDeque<Record>,
+    first_timestamp: u64,
+    // set on the first poll so that loading the game doesn't eat into the timing
+    started: Option<Instant>,
+    // moves recorded client timestamps to the current time
+    time_shift: u64,
+}
+
+impl FileTransport {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let mut input = BufReader::new(File::open(path)?);
+        let mut records = VecDeque::new();
+
+        while let Some(record) = Record::read(&mut input)? {
+            records.push_back(record);
+        }
+
+        let first_timestamp = records.front()
+            .map(|record| record.timestamp)
+            .unwrap_or(0);
+
+        Ok(Self {
+            records,
+            first_timestamp,
+            started: None,
+            time_shift: 0,
+        })
+    }
+
+    fn decode(&self, record: Record) -> Option<Msg> {
+        match record.kind {
+            recording::KIND_PACKET => {
+                let msg = match Msg::from_bytes(&record.payload) {
+                    // latency is measured against the client clock at recording time
+                    Ok(Msg::Latency(mut measure)) => {
+                        measure.timestamp = measure.timestamp.wrapping_add(self.time_shift);
+                        Msg::Latency(measure)
+                    }
+                    Ok(msg) => msg,
+                    Err(err) => Msg::Malformed(err),
+                };
+
+                Some(msg)
+            }
+            recording::KIND_SERVER_NOT_RESPONDING => Some(Msg::ServerNotResponding),
+            recording::KIND_INPUT => {
+                match astero::Input::decode(record.payload) {
+                    Ok(input) => Some(Msg::ReplayedInput(input)),
+                    Err(err) => {
+                        println!("Skipping broken input in recording: {}", err);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Transport for FileTransport {
+    fn send(&mut self, _msg: Msg) {}
+
+    fn try_recv(&mut self) -> Option<Msg> {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+            self.time_shift = util::cur_time_in_millis().wrapping_sub(self.first_timestamp);
+        }
+
+        let started = self.started.expect("Set above");
+        let elapsed = util::duration_to_millis(started.elapsed());
+
+        loop {
+            let due = self.records.front()
+                .map(|record| record.timestamp.saturating_sub(self.first_timestamp) <= elapsed)
+                .unwrap_or(false);
+
+            if !due {
+                return None;
+            }
+
+            let record = self.records.pop_front().expect("Checked above");
+            if let Some(msg) = self.decode(record) {
+                return Some(msg);
+            }
+        }
+    }
+
+    fn stop(&mut self) {}
+}
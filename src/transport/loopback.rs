@@ -0,0 +1,63 @@
+use std::sync::mpsc::{
+    self,
+    Receiver,
+    Sender,
+};
+
+use msg::Msg;
+use transport::Transport;
+
+
+/// In-memory transport for tests. Whatever the client sends shows up at the paired `LoopbackPeer`
+/// and whatever the peer sends is received by the client, in order and without loss.
+pub struct LoopbackTransport {
+    to_peer: Sender<Msg>,
+    from_peer: Receiver<Msg>,
+}
+
+pub struct LoopbackPeer {
+    to_client: Sender<Msg>,
+    from_client: Receiver<Msg>,
+}
+
+impl LoopbackTransport {
+    pub fn pair() -> (Self, LoopbackPeer) {
+        let (to_peer, from_client) = mpsc::channel();
+        let (to_client, from_peer) = mpsc::channel();
+
+        let transport = Self {
+            to_peer,
+            from_peer,
+        };
+
+        let peer = LoopbackPeer {
+            to_client,
+            from_client,
+        };
+
+        (transport, peer)
+    }
+}
+
+impl Transport for LoopbackTransport {
+    fn send(&mut self, msg: Msg) {
+        // peer may be gone already, just like a server
+        let _ = self.to_peer.send(msg);
+    }
+
+    fn try_recv(&mut self) -> Option<Msg> {
+        self.from_peer.try_recv().ok()
+    }
+
+    fn stop(&mut self) {}
+}
+
+impl LoopbackPeer {
+    pub fn send(&self, msg: Msg) {
+        let _ = self.to_client.send(msg);
+    }
+
+    pub fn try_recv(&self) -> Option<Msg> {
+        self.from_client.try_recv().ok()
+    }
+}
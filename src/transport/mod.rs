@@ -0,0 +1,31 @@
+mod file;
+#[cfg(test)]
+mod loopback;
+mod recording;
+mod udp;
+
+pub use self::file::FileTransport;
+#[cfg(test)]
+pub use self::loopback::{
+    LoopbackPeer,
+    LoopbackTransport,
+};
+pub use self::recording::RecordingTransport;
+pub use self::udp::UdpTransport;
+
+use msg::Msg;
+
+
+/// Carries messages between the client and the server.
+/// Connection management (heartbeats, timeouts, reconnects) is done by `resources::Client` on top.
+pub trait Transport {
+    fn send(&mut self, msg: Msg);
+    fn try_recv(&mut self) -> Option<Msg>;
+    fn stop(&mut self);
+
+    /// Datagram the message last returned by `try_recv` was decoded from,
+    /// `None` if it didn't come from the network.
+    fn last_datagram(&self) -> Option<&[u8]> {
+        None
+    }
+}
@@ -0,0 +1,216 @@
+use std::fs::File;
+use std::io::{
+    self,
+    BufWriter,
+    Read,
+    Write,
+};
+
+use msg::Msg;
+use proto::astero;
+use transport::Transport;
+use util::cur_time_in_millis;
+
+
+use prost::Message;
+
+
+// Recording is a sequence of records:
+//   kind                        - u8, one of the constants below
+//   timestamp in millis         - u64, big endian
+//   payload length              - u32, big endian
+//   payload
+pub const KIND_PACKET: u8 = 0;
+pub const KIND_SERVER_NOT_RESPONDING: u8 = 1;
+pub const KIND_INPUT: u8 = 2;
+
+// nothing longer fits in a UDP datagram, a longer record means a broken file
+const MAX_PAYLOAD_LEN: usize = 65_535;
+
+
+pub struct Record {
+    pub kind: u8,
+    pub timestamp: u64,
+    // datagram as received for packets, encoded astero::Input for inputs
+    pub payload: Vec<u8>,
+}
+
+impl Record {
+    pub fn read<R: Read>(input: &mut R) -> io::Result<Option<Self>> {
+        let mut kind = [0; 1];
+        match input.read_exact(&mut kind) {
+            Ok(()) => {}
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        }
+
+        let mut timestamp = [0; 8];
+        input.read_exact(&mut timestamp)?;
+
+        let mut len = [0; 4];
+        input.read_exact(&mut len)?;
+
+        let len = u32_from_be(len) as usize;
+        if len > MAX_PAYLOAD_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("record of {} bytes, at most {} expected", len, MAX_PAYLOAD_LEN),
+            ));
+        }
+
+        let mut payload = vec![0; len];
+        input.read_exact(&mut payload)?;
+
+        Ok(Some(Self {
+            kind: kind[0],
+            timestamp: u64_from_be(timestamp),
+            payload,
+        }))
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(&[self.kind])?;
+        out.write_all(&u64_to_be(self.timestamp))?;
+        out.write_all(&u32_to_be(self.payload.len() as u32))?;
+        out.write_all(&self.payload)
+    }
+}
+
+
+/// Wraps another transport and writes every datagram received from it
+/// (malformed ones too), and every input sent through it, to a recording.
+pub struct RecordingTransport {
+    inner: Box<Transport>,
+    out: BufWriter<File>,
+}
+
+impl RecordingTransport {
+    pub fn create(inner: Box<Transport>, path: &str) -> io::Result<Self> {
+        Ok(Self {
+            inner,
+            out: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    fn record(&mut self, kind: u8, payload: Vec<u8>) {
+        let record = Record {
+            kind,
+            timestamp: cur_time_in_millis(),
+            payload,
+        };
+
+        if let Err(err) = record.write(&mut self.out) {
+            println!("Failed to write recording: {}", err);
+        }
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn send(&mut self, msg: Msg) {
+        if let Msg::ToServer(astero::client::Msg::Input(ref input)) = msg {
+            let mut payload = Vec::new();
+            input.encode(&mut payload)
+                .expect("Failed to encode input");
+
+            self.record(KIND_INPUT, payload);
+        }
+
+        self.inner.send(msg);
+    }
+
+    fn try_recv(&mut self) -> Option<Msg> {
+        let msg = self.inner.try_recv();
+
+        match msg {
+            Some(Msg::ServerNotResponding) => self.record(KIND_SERVER_NOT_RESPONDING, Vec::new()),
+            Some(..) => {
+                let datagram = self.inner.last_datagram().map(|datagram| datagram.to_vec());
+                if let Some(datagram) = datagram {
+                    self.record(KIND_PACKET, datagram);
+                }
+            }
+            None => {}
+        }
+
+        msg
+    }
+
+    fn stop(&mut self) {
+        if let Err(err) = self.out.flush() {
+            println!("Failed to write recording: {}", err);
+        }
+
+        self.inner.stop();
+    }
+}
+
+
+fn u64_to_be(value: u64) -> [u8; 8] {
+    let mut bytes = [0; 8];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (value >> (56 - 8 * i)) as u8;
+    }
+
+    bytes
+}
+
+fn u64_from_be(bytes: [u8; 8]) -> u64 {
+    bytes.iter().fold(0, |value, &byte| (value << 8) | u64::from(byte))
+}
+
+fn u32_to_be(value: u32) -> [u8; 4] {
+    let mut bytes = [0; 4];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (value >> (24 - 8 * i)) as u8;
+    }
+
+    bytes
+}
+
+fn u32_from_be(bytes: [u8; 4]) -> u32 {
+    bytes.iter().fold(0, |value, &byte| (value << 8) | u32::from(byte))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::{
+        Cursor,
+        ErrorKind,
+    };
+
+    use super::{
+        KIND_PACKET,
+        MAX_PAYLOAD_LEN,
+        Record,
+        u32_to_be,
+    };
+
+
+    #[test]
+    fn record_is_read_back() {
+        let mut bytes = Vec::new();
+        Record { kind: KIND_PACKET, timestamp: 1234, payload: vec![1, 2, 3] }.write(&mut bytes)
+            .expect("Failed to write record");
+
+        let mut input = Cursor::new(bytes);
+        let record = Record::read(&mut input)
+            .expect("Failed to read record")
+            .expect("No record");
+
+        assert_eq!((record.kind, record.timestamp, record.payload), (KIND_PACKET, 1234, vec![1, 2, 3]));
+        assert!(Record::read(&mut input).expect("Failed to read end").is_none());
+    }
+
+    #[test]
+    fn oversized_record_is_rejected() {
+        let mut bytes = vec![KIND_PACKET, 0, 0, 0, 0, 0, 0, 0, 0];
+        bytes.extend_from_slice(&u32_to_be(MAX_PAYLOAD_LEN as u32 + 1));
+
+        match Record::read(&mut Cursor::new(bytes)) {
+            Err(ref err) if err.kind() == ErrorKind::InvalidData => {}
+            Err(err) => panic!("Unexpected error {}", err),
+            Ok(_) => panic!("Oversized record was read"),
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std;
+use std::io;
+use std::net::{SocketAddr, Ipv4Addr, Ipv6Addr, IpAddr};
+use std::time::Duration;
+use std::thread;
+
+use futures;
+use tokio_core::{
+    net::UdpSocket,
+    reactor::Core,
+    reactor::Interval,
+};
+
+use msg::Msg;
+use proto::mmob;
+use transport::Transport;
+use util::cur_time_in_millis;
+
+
+use futures::{
+    Stream,
+    Sink,
+    Future,
+};
+use tokio_core::net::UdpCodec;
+
+
+struct ClientCodec {
+    server: SocketAddr,
+}
+
+impl ClientCodec {
+    pub fn new(server: SocketAddr) -> Self {
+        Self {
+            server,
+        }
+    }
+}
+
+impl UdpCodec for ClientCodec {
+    // message and the datagram it came in
+    type In = (Msg, Option<Vec<u8>>);
+    type Out = Msg;
+
+    fn decode(&mut self, src: &SocketAddr, buf: &[u8]) -> io::Result<Self::In> {
+        if *src != self.server {
+            return Ok((Msg::Unknown, None));
+        }
+
+        Ok((Msg::from_bytes(buf).unwrap_or_else(Msg::Malformed), Some(buf.to_vec())))
+    }
+
+    fn encode(&mut self, msg: Self::Out, buf: &mut Vec<u8>) -> SocketAddr {
+        // only messages meant for the server are sent
+        let bytes = msg.to_client_bytes()
+            .expect("Failed to encode message");
+        buf.extend_from_slice(&bytes);
+
+        self.server
+    }
+}
+
+
+/// Talks to the server over UDP from a separate reactor thread.
+/// Also emits `ServerNotResponding` ticks and periodic latency measures.
+pub struct UdpTransport {
+    thread_handle: Option<thread::JoinHandle<()>>,
+    to: Option<futures::sync::mpsc::UnboundedSender<Msg>>,
+    from: std::sync::mpsc::Receiver<(Msg, Option<Vec<u8>>)>,
+    stop: Option<futures::sync::oneshot::Sender<()>>,
+    // kept for `RecordingTransport`
+    last_datagram: Option<Vec<u8>>,
+}
+
+impl UdpTransport {
+    pub fn start(server: SocketAddr) -> io::Result<Self> {
+        // Bind in the calling thread so that failures are reported to the caller
+        // instead of panicking inside the reactor thread.
+        let client_address = match server {
+            SocketAddr::V4(..) => SocketAddr::new(IpAddr::V4(Ipv4Addr::unspecified()), 0),
+            SocketAddr::V6(..) => SocketAddr::new(IpAddr::V6(Ipv6Addr::unspecified()), 0),
+        };
+        let socket = std::net::UdpSocket::bind(&client_address)?;
+
+        let (to_main_thread, from_client) = std::sync::mpsc::channel();
+        let (to_client, from_main_thread) = futures::sync::mpsc::unbounded();
+        let (stop_sender, stop_receiver) = futures::sync::oneshot::channel();
+
+        let thread_handle = thread::spawn(move || {
+            let mut reactor = Core::new().expect("Failed to init reactor");
+            let handle = reactor.handle();
+
+            let socket =
+                UdpSocket::from_socket(socket, &handle)
+                    .expect("Failed to register socket in reactor");
+
+            let (outgoing, ingoing) =
+                socket.framed(ClientCodec::new(server)).split();
+
+            // Stream of timeouts. Selected with network messages.
+            // If timeout comes first it means that server is not sending any data.
+            let timeouts =
+                Interval::new(Duration::new(6, 0), &handle)
+                .expect("Failed to setup timeouts")
+                .map(|_| (Msg::ServerNotResponding, None));
+
+            let ingoing = ingoing.select(timeouts);
+
+            let receiver = ingoing.for_each(move |received| {
+                to_main_thread.send(received).expect("Failed to drop message to the main thread");
+                Ok(())
+            }).map_err(|err| panic!("{}", err));
+
+            let from_main_thread = from_main_thread
+                .map_err(|_err| -> io::Error {
+                    io::ErrorKind::Other.into()
+                });
+
+            let latency_measures =
+                Interval::new(Duration::new(1, 0), &handle)
+                    .expect("Failed to setup latency measures")
+                    .map(|_| {
+                        Msg::Latency(mmob::LatencyMeasure {
+                            timestamp: cur_time_in_millis(),
+                            server_timestamp: None,
+                        })
+                    });
+
+            let sender = outgoing.send_all(from_main_thread.select(latency_measures));
+
+            let client = sender.join(receiver).select2(stop_receiver);
+            reactor.run(client).ok().expect("Client thread failure");
+        });
+
+        Ok(Self {
+            thread_handle: Some(thread_handle),
+            to: Some(to_client),
+            from: from_client,
+            stop: Some(stop_sender),
+            last_datagram: None,
+        })
+    }
+
+}
+
+impl Transport for UdpTransport {
+    fn send(&mut self, msg: Msg) {
+        self.to
+            .as_ref()
+            .and_then(|s| {
+                s.unbounded_send(msg).expect("Failed to send message to client thread");
+                Some(())
+            });
+    }
+
+    fn try_recv(&mut self) -> Option<Msg> {
+        let (msg, datagram) = self.from.try_recv().ok()?;
+        self.last_datagram = datagram;
+
+        Some(msg)
+    }
+
+    fn stop(&mut self) {
+        self.stop
+            .take()
+            .expect("Absent stop sender?! What?!")
+            .send(())
+            .expect("Failed to stop client reactor");
+
+        self.thread_handle
+            .take()
+            .expect("Absent thread handle?! What?")
+            .join()
+            .expect("Failed to join client thread");
+    }
+
+    fn last_datagram(&self) -> Option<&[u8]> {
+        self.last_datagram.as_ref().map(Vec::as_slice)
+    }
+}
@@ -1,4 +1,5 @@
 use std;
+use std::time::Duration;
 use ggez::graphics::{Vector2, Point2};
 use time;
 
@@ -35,6 +36,33 @@ pub fn vec_from_angle(angle: f32) -> Vector2 {
     Vector2::new(angle.sin(), angle.cos())
 }
 
+/// Same angle in `(-PI, PI]`.
+pub fn normalize_angle(angle: f32) -> f32 {
+    use std::f32::consts::PI;
+
+    // keeps the sign, so it's in `(-2PI, 2PI)` here
+    let angle = angle % (2.0 * PI);
+    if angle > PI {
+        angle - 2.0 * PI
+    } else if angle <= -PI {
+        angle + 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+/// Interpolates between two angles along the shorter arc, the result is normalized.
+/// `t` is clamped to `0..1`, the ends give back `from` and `to` exactly.
+pub fn lerp_angle(from: f32, to: f32, t: f32) -> f32 {
+    if t <= 0.0 {
+        normalize_angle(from)
+    } else if t >= 1.0 {
+        normalize_angle(to)
+    } else {
+        normalize_angle(from + normalize_angle(to - from) * t)
+    }
+}
+
 pub fn reflect_vector(vec: Vector2, normal: Vector2) -> Vector2 {
     // |normal| ^ 2 == 1.0
     assert!(
@@ -52,6 +80,14 @@ pub fn cur_time_in_millis() -> u64 {
     millis as u64
 }
 
+pub fn duration_to_secs(duration: Duration) -> f32 {
+    duration.as_secs() as f32 + duration.subsec_nanos() as f32 / 1_000_000_000.0
+}
+
+pub fn duration_to_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_nanos()) / 1_000_000
+}
+
 pub fn cur_user_name() -> String {
     std::env::home_dir()
         .expect("Failed to retrieve home dir")
@@ -62,3 +98,122 @@ pub fn cur_user_name() -> String {
         .expect("Failed to convert username to Unicode")
         .to_string()
 }
+
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::{
+        lerp_angle,
+        normalize_angle,
+    };
+
+
+    // angles every few degrees over several turns both ways, plus the edges
+    fn angles() -> Vec<f32> {
+        let mut angles: Vec<f32> = (-400..400)
+            .map(|i| i as f32 * 0.07)
+            .collect();
+
+        for turns in -3..4 {
+            let base = turns as f32 * 2.0 * PI;
+            angles.extend_from_slice(&[base - PI, base + PI, base, base + 1e-4, base - 1e-4]);
+        }
+
+        angles
+    }
+
+    // fewer of them, for pairs
+    fn every(n: usize) -> Vec<f32> {
+        angles().into_iter()
+            .enumerate()
+            .filter(|&(i, _)| i % n == 0)
+            .map(|(_, angle)| angle)
+            .collect()
+    }
+
+    // shortest way from `a` to `b`, positive counterclockwise
+    fn difference(a: f32, b: f32) -> f32 {
+        let diff = (f64::from(b) - f64::from(a)) % (2.0 * ::std::f64::consts::PI);
+        if diff > ::std::f64::consts::PI {
+            (diff - 2.0 * ::std::f64::consts::PI) as f32
+        } else if diff <= -::std::f64::consts::PI {
+            (diff + 2.0 * ::std::f64::consts::PI) as f32
+        } else {
+            diff as f32
+        }
+    }
+
+    #[test]
+    fn normalized_angle_is_in_range() {
+        for angle in angles() {
+            let normalized = normalize_angle(angle);
+
+            assert!(normalized > -PI && normalized <= PI, "{} normalized to {}", angle, normalized);
+            assert!(difference(angle, normalized).abs() < 1e-3, "{} normalized to {}", angle, normalized);
+        }
+    }
+
+    #[test]
+    fn half_turn_is_positive() {
+        assert!((normalize_angle(PI) - PI).abs() < 1e-6);
+        assert!((normalize_angle(-PI) - PI).abs() < 1e-6);
+        assert!(PI - normalize_angle(3.0 * PI).abs() < 1e-5);
+    }
+
+    #[test]
+    fn normalized_angle_is_kept() {
+        for angle in angles().into_iter().map(normalize_angle) {
+            assert_eq!(normalize_angle(angle).to_bits(), angle.to_bits());
+        }
+    }
+
+    #[test]
+    fn lerp_ends_are_exact() {
+        for from in every(7).into_iter().map(normalize_angle) {
+            for to in every(11).into_iter().map(normalize_angle) {
+                assert_eq!(lerp_angle(from, to, 0.0).to_bits(), from.to_bits());
+                assert_eq!(lerp_angle(from, to, 1.0).to_bits(), to.to_bits());
+            }
+        }
+    }
+
+    #[test]
+    fn lerp_takes_the_short_way() {
+        for from in every(7) {
+            for to in every(11) {
+                let total = difference(from, to);
+                if PI - total.abs() < 1e-3 {
+                    // opposite angles, both ways are as short
+                    continue;
+                }
+
+                for &t in &[0.25, 0.5, 0.75] {
+                    let lerped = lerp_angle(from, to, t);
+
+                    assert!(lerped > -PI && lerped <= PI);
+                    // how far it went from `from`, and never the long way round
+                    assert!(
+                        (difference(from, lerped) - total * t).abs() < 1e-3,
+                        "{} to {} at {} gave {}", from, to, t, lerped
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lerp_across_half_turn() {
+        let from = PI - 0.1;
+        let to = -PI + 0.1;
+
+        // through PI, not through zero
+        let middle = lerp_angle(from, to, 0.5);
+        assert!(PI - middle.abs() < 1e-4, "{}", middle);
+
+        let quarter = lerp_angle(from, to, 0.25);
+        assert!((quarter - (PI - 0.05)).abs() < 1e-4, "{}", quarter);
+        assert!((lerp_angle(to, from, 0.25) - (-PI + 0.05)).abs() < 1e-4);
+    }
+}
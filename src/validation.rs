@@ -0,0 +1,173 @@
+//! Checks server messages before they are turned into components.
+//! Missing optional fields get defaults, anything that can't be defaulted
+//! rejects the single entity with a `Violation` instead of aborting the game.
+
+use std::fmt;
+
+use components::Body;
+use constant;
+use proto::astero;
+
+
+#[derive(Debug)]
+pub enum Violation {
+    EmptyCreate,
+    EmptyUpdate(u32),
+    MissingLastHandledInput(u32),
+    MissingServerTimestamp,
+    InvalidBody(u32),
+    InvalidLife(u32, f32),
+    // update kind doesn't match the entity created for this id
+    KindMismatch(u32),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::EmptyCreate => write!(f, "create without entity"),
+            Violation::EmptyUpdate(id) => write!(f, "update without entity for id {}", id),
+            Violation::MissingLastHandledInput(id) => write!(f, "player update without last handled input for id {}", id),
+            Violation::MissingServerTimestamp => write!(f, "latency measure without server timestamp"),
+            Violation::InvalidBody(id) => write!(f, "non-finite body for id {}", id),
+            Violation::InvalidLife(id, life) => write!(f, "invalid life {} for id {}", life, id),
+            Violation::KindMismatch(id) => write!(f, "update kind doesn't match entity with id {}", id),
+        }
+    }
+}
+
+
+pub struct Player {
+    pub id: u32,
+    pub body: Body,
+    pub life: f32,
+    pub nickname: String,
+}
+
+pub struct Asteroid {
+    pub id: u32,
+    pub body: Body,
+    pub life: f32,
+}
+
+pub struct Shot {
+    pub body: Body,
+    pub ttl: f32,
+}
+
+pub enum Create {
+    Player(Player),
+    Asteroid(Asteroid),
+    Shot(Shot),
+}
+
+
+#[derive(Clone)]
+pub struct PlayerUpdate {
+    pub id: u32,
+    pub body: Body,
+    pub current_fire_timeout: Option<f32>,
+    pub last_handled_input: u32,
+    pub shot_confirmed: bool,
+    pub life: Option<f32>,
+}
+
+pub struct AsteroidUpdate {
+    pub body: Body,
+    pub life: Option<f32>,
+}
+
+pub enum Update {
+    Player(PlayerUpdate),
+    Asteroid(AsteroidUpdate),
+}
+
+
+pub fn player(player: &astero::Player) -> Result<Player, Violation> {
+    Ok(Player {
+        id: player.id,
+        body: body(player.id, &player.body)?,
+        life: life(player.id, player.life, constant::defaults::PLAYER_LIFE)?,
+        nickname: match player.nickname {
+            Some(ref nickname) if !nickname.is_empty() => nickname.clone(),
+            _ => format!("Player {}", player.id),
+        },
+    })
+}
+
+pub fn create(create: &astero::Create) -> Result<Create, Violation> {
+    let entity = create.entity.as_ref().ok_or(Violation::EmptyCreate)?;
+
+    let create = match *entity {
+        astero::create::Entity::Player(ref other) => Create::Player(player(other)?),
+        astero::create::Entity::Asteroid(ref asteroid) => Create::Asteroid(Asteroid {
+            id: asteroid.id,
+            body: body(asteroid.id, &asteroid.body)?,
+            life: life(asteroid.id, asteroid.life, constant::defaults::ASTEROID_LIFE)?,
+        }),
+        astero::create::Entity::Shot(ref shot) => Create::Shot(Shot {
+            // shots have no network id
+            body: body(0, &shot.body)?,
+            ttl: if shot.ttl.is_finite() && shot.ttl > 0.0 { shot.ttl } else { constant::shot::TTL },
+        }),
+    };
+
+    Ok(create)
+}
+
+pub fn update(id: u32, update: &astero::Update) -> Result<Update, Violation> {
+    let entity = update.entity.as_ref().ok_or(Violation::EmptyUpdate(id))?;
+
+    let update = match *entity {
+        astero::update::Entity::Player(ref player) => Update::Player(PlayerUpdate {
+            id: player.id,
+            body: body(player.id, &player.body)?,
+            current_fire_timeout: player.current_fire_timeout
+                .and_then(|timeout| if timeout.is_finite() { Some(timeout) } else { None }),
+            last_handled_input: player.last_handled_input
+                .ok_or(Violation::MissingLastHandledInput(player.id))?,
+            shot_confirmed: player.shot_confirmed.unwrap_or(true),
+            life: updated_life(player.id, player.life)?,
+        }),
+        astero::update::Entity::Asteroid(ref asteroid) => Update::Asteroid(AsteroidUpdate {
+            body: body(id, &asteroid.body)?,
+            life: updated_life(id, asteroid.life)?,
+        }),
+    };
+
+    Ok(update)
+}
+
+pub fn server_timestamp(server_timestamp: Option<u64>) -> Result<u64, Violation> {
+    server_timestamp.ok_or(Violation::MissingServerTimestamp)
+}
+
+pub fn body(id: u32, body: &astero::Body) -> Result<Body, Violation> {
+    let body = Body::new(body);
+
+    let finite = body.pos.x.is_finite() && body.pos.y.is_finite() &&
+        body.vel.x.is_finite() && body.vel.y.is_finite() &&
+        body.rot.is_finite() && body.rvel.is_finite() &&
+        body.size.is_finite() && body.size >= 0.0;
+
+    if finite {
+        Ok(body)
+    } else {
+        Err(Violation::InvalidBody(id))
+    }
+}
+
+fn life(id: u32, life: Option<f32>, default: f32) -> Result<f32, Violation> {
+    match life {
+        None => Ok(default),
+        Some(life) if life.is_finite() && life > 0.0 => Ok(life),
+        Some(life) => Err(Violation::InvalidLife(id, life)),
+    }
+}
+
+// unlike on create, zero is fine: the entity is about to be destroyed
+fn updated_life(id: u32, life: Option<f32>) -> Result<Option<f32>, Violation> {
+    match life {
+        Some(life) if !life.is_finite() || life < 0.0 => Err(Violation::InvalidLife(id, life)),
+        life => Ok(life),
+    }
+}